@@ -0,0 +1,326 @@
+pub mod curation_report_mod {
+    use std::collections::{HashMap, HashSet};
+    use std::fmt;
+    use std::io::Write;
+
+    use crate::profile::profile_mod::nx_lx;
+    use crate::tpf_fasta::tpf_fasta_mod::{
+        check_layout, group_by_scaffold, natural_cmp, Tpf, TpfEntry,
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum ChangeKind {
+        Break,
+        Join,
+        Inversion,
+        Moved,
+        Dropped,
+        Duplicated,
+    }
+
+    impl fmt::Display for ChangeKind {
+        fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let name = match self {
+                ChangeKind::Break => "BREAK",
+                ChangeKind::Join => "JOIN",
+                ChangeKind::Inversion => "INVERSION",
+                ChangeKind::Moved => "MOVED",
+                ChangeKind::Dropped => "DROPPED",
+                ChangeKind::Duplicated => "DUPLICATED",
+            };
+            write!(fmt, "{}", name)
+        }
+    }
+
+    // One line of the report, where it was in the original
+    // assembly and where it ended up
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Change {
+        pub kind: ChangeKind,
+        pub original: String,
+        pub curated: String,
+        pub detail: String,
+        // Bases of the original region, 0 for the point of a break
+        pub size: usize,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct AssemblyStats {
+        pub scaffolds: usize,
+        pub total_length: usize,
+        pub n50: usize,
+    }
+
+    impl AssemblyStats {
+        pub fn from_lengths(lengths: &[usize]) -> AssemblyStats {
+            AssemblyStats {
+                scaffolds: lengths.len(),
+                total_length: lengths.iter().sum(),
+                n50: nx_lx(lengths, 0.5).0,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CurationReport {
+        pub changes: Vec<Change>,
+        pub before: AssemblyStats,
+        pub after: AssemblyStats,
+    }
+
+    fn region(tpf: &Tpf) -> String {
+        format!("{}:{}-{}", tpf.ori_scaffold, tpf.start_coord, tpf.end_coord)
+    }
+
+    fn size(tpf: &Tpf) -> usize {
+        tpf.end_coord + 1 - tpf.start_coord
+    }
+
+    fn continues(previous: &Tpf, next: &Tpf) -> bool {
+        // next carries straight on from previous as it was in the original
+        previous.ori_scaffold == next.ori_scaffold
+            && previous.orientation == next.orientation
+            && if previous.orientation == "MINUS" {
+                previous.start_coord == next.end_coord + 1
+            } else {
+                next.start_coord == previous.end_coord + 1
+            }
+    }
+
+    impl CurationReport {
+        pub fn count(&self, kind: ChangeKind) -> usize {
+            self.changes.iter().filter(|x| x.kind == kind).count()
+        }
+
+        fn bases(&self, kind: ChangeKind) -> usize {
+            self.changes
+                .iter()
+                .filter(|x| x.kind == kind)
+                .map(|x| x.size)
+                .sum()
+        }
+
+        pub fn write_tsv(&self, out: &mut dyn Write) -> std::io::Result<()> {
+            //
+            // One change per line, then the before (original) and
+            // after (curated) stats of the assemblies
+            //
+            writeln!(out, "#type\toriginal\tcurated\tdetail")?;
+            for change in &self.changes {
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}",
+                    change.kind, change.original, change.curated, change.detail
+                )?;
+            }
+            writeln!(
+                out,
+                "SCAFFOLD_COUNT\t{}\t{}\t-",
+                self.before.scaffolds, self.after.scaffolds
+            )?;
+            writeln!(
+                out,
+                "TOTAL_LENGTH\t{}\t{}\t-",
+                self.before.total_length, self.after.total_length
+            )?;
+            writeln!(out, "N50\t{}\t{}\t-", self.before.n50, self.after.n50)
+        }
+    }
+
+    impl fmt::Display for CurationReport {
+        fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(fmt, "CURATION REPORT")?;
+            writeln!(fmt, "\tBreaks:\t\t{}", self.count(ChangeKind::Break))?;
+            writeln!(fmt, "\tJoins:\t\t{}", self.count(ChangeKind::Join))?;
+            writeln!(fmt, "\tInversions:\t{}", self.count(ChangeKind::Inversion))?;
+            writeln!(fmt, "\tMoved:\t\t{}", self.count(ChangeKind::Moved))?;
+            writeln!(
+                fmt,
+                "\tDropped:\t{} regions ({} bp)",
+                self.count(ChangeKind::Dropped),
+                self.bases(ChangeKind::Dropped)
+            )?;
+            writeln!(
+                fmt,
+                "\tDuplicated:\t{} regions ({} bp)",
+                self.count(ChangeKind::Duplicated),
+                self.bases(ChangeKind::Duplicated)
+            )?;
+            writeln!(fmt, "\t\t\tOriginal\tCurated")?;
+            writeln!(
+                fmt,
+                "\tScaffolds:\t{}\t\t{}",
+                self.before.scaffolds, self.after.scaffolds
+            )?;
+            writeln!(
+                fmt,
+                "\tTotal length:\t{}\t\t{}",
+                self.before.total_length, self.after.total_length
+            )?;
+            writeln!(fmt, "\tN50:\t\t{}\t\t{}", self.before.n50, self.after.n50)
+        }
+    }
+
+    fn most_of<'a>(pieces: &[&'a Tpf], key: impl Fn(&'a Tpf) -> &'a String) -> &'a String {
+        // The key holding the most bases of the pieces, ties go to the first seen
+        let mut totals: Vec<(&String, usize)> = Vec::new();
+        for tpf in pieces {
+            match totals.iter_mut().find(|x| x.0 == key(tpf)) {
+                Some(x) => x.1 += size(tpf),
+                None => totals.push((key(tpf), size(tpf))),
+            }
+        }
+        totals
+            .iter()
+            .fold(totals[0], |best, x| if x.1 > best.1 { *x } else { best })
+            .0
+    }
+
+    pub fn curation_report(
+        entries: &[TpfEntry],
+        lengths: &HashMap<String, usize>,
+        n_length: usize,
+    ) -> CurationReport {
+        //
+        // Compare the layout to the original assembly.
+        // A join is two neighbouring components of a new scaffold which
+        // were not neighbours (in the same orientation, with no gap) in
+        // the original, a break is two neighbouring pieces of an original
+        // scaffold which are no longer neighbours in the curated assembly.
+        // Pieces of an original scaffold which end up away from where most
+        // of it went are moved, and those in the other orientation to most
+        // of it are inversions.
+        //
+        let mut changes: Vec<Change> = Vec::new();
+        let mut continued: HashSet<(&String, usize)> = HashSet::new();
+        let mut after_lengths: Vec<usize> = Vec::new();
+
+        for (scaffold, scaffold_entries) in group_by_scaffold(entries) {
+            after_lengths.push(scaffold_entries.iter().map(|x| x.length(n_length)).sum());
+
+            let mut previous: Option<&Tpf> = None;
+            let mut after_gap = false;
+            for entry in scaffold_entries {
+                let tpf = match entry {
+                    TpfEntry::Gap(_) => {
+                        after_gap = true;
+                        continue;
+                    }
+                    TpfEntry::Component(tpf) => tpf,
+                };
+
+                if let Some(last) = previous {
+                    if !after_gap && continues(last, tpf) {
+                        // Remember the end of the lower of the two pieces
+                        continued.insert((&tpf.ori_scaffold, last.end_coord.min(tpf.end_coord)));
+                    } else {
+                        changes.push(Change {
+                            kind: ChangeKind::Join,
+                            original: region(last),
+                            curated: scaffold.to_owned(),
+                            detail: format!(
+                                "to {}{}",
+                                region(tpf),
+                                if after_gap { " with a gap" } else { "" }
+                            ),
+                            size: size(last),
+                        });
+                    }
+                }
+                previous = Some(tpf);
+                after_gap = false;
+            }
+        }
+
+        // The pieces of each original scaffold in coordinate order
+        let mut pieces: HashMap<&String, Vec<&Tpf>> = HashMap::new();
+        for entry in entries {
+            if let TpfEntry::Component(tpf) = entry {
+                pieces.entry(&tpf.ori_scaffold).or_default().push(tpf);
+            }
+        }
+        let mut originals: Vec<&String> = pieces.keys().copied().collect();
+        originals.sort_by(|a, b| natural_cmp(a, b));
+
+        for original in originals {
+            let mut scaffold_pieces = pieces[original].to_owned();
+            scaffold_pieces.sort_by_key(|x| (x.start_coord, x.end_coord));
+
+            for pair in scaffold_pieces.windows(2) {
+                if !continued.contains(&(original, pair[0].end_coord)) {
+                    changes.push(Change {
+                        kind: ChangeKind::Break,
+                        original: format!("{}:{}", original, pair[0].end_coord),
+                        curated: "-".to_string(),
+                        detail: format!(
+                            "{} in {} | {} in {}",
+                            region(pair[0]),
+                            pair[0].new_scaffold,
+                            region(pair[1]),
+                            pair[1].new_scaffold
+                        ),
+                        size: 0,
+                    });
+                }
+            }
+
+            // Most of the original scaffold went here, and in this
+            // orientation, ties go to the first seen
+            let home = most_of(&pieces[original], |x| &x.new_scaffold);
+            let home_orientation = most_of(&pieces[original], |x| &x.orientation);
+            for tpf in &pieces[original] {
+                if tpf.new_scaffold != *home {
+                    changes.push(Change {
+                        kind: ChangeKind::Moved,
+                        original: region(tpf),
+                        curated: tpf.new_scaffold.to_owned(),
+                        detail: format!("from {}", home),
+                        size: size(tpf),
+                    });
+                }
+                // Flipped against the rest of its original scaffold, a
+                // scaffold flipped as a whole has no inversions
+                if tpf.orientation != *home_orientation {
+                    changes.push(Change {
+                        kind: ChangeKind::Inversion,
+                        original: region(tpf),
+                        curated: tpf.new_scaffold.to_owned(),
+                        detail: format!("{} in {}", tpf.orientation, home_orientation),
+                        size: size(tpf),
+                    });
+                }
+            }
+        }
+
+        // Dropped and duplicated sequence is what the layout check calls
+        // uncovered and duplicated
+        let numbered: Vec<(usize, TpfEntry)> = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (index + 1, entry.to_owned()))
+            .collect();
+        let layout = check_layout(&numbered, lengths);
+        for (kind, regions) in [
+            (ChangeKind::Dropped, &layout.uncovered),
+            (ChangeKind::Duplicated, &layout.duplicated),
+        ] {
+            for coverage in regions {
+                let size = coverage.end + 1 - coverage.start;
+                changes.push(Change {
+                    kind,
+                    original: format!("{}:{}-{}", coverage.scaffold, coverage.start, coverage.end),
+                    curated: "-".to_string(),
+                    detail: format!("{} bp", size),
+                    size,
+                });
+            }
+        }
+
+        let before_lengths: Vec<usize> = lengths.values().copied().collect();
+        CurationReport {
+            changes,
+            before: AssemblyStats::from_lengths(&before_lengths),
+            after: AssemblyStats::from_lengths(&after_lengths),
+        }
+    }
+}
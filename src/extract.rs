@@ -0,0 +1,262 @@
+pub mod extract_mod {
+    use clap::ArgMatches;
+    use colored::Colorize;
+    use noodles::core::{Position, Region};
+    use noodles::fasta;
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::io::{BufRead, Seek, Write};
+
+    use crate::faidx::faidx_mod::parse_region;
+    use crate::generics::{open_indexed_fasta, open_reader, output_compression, FastaOutput};
+    use crate::tpf_fasta::tpf_fasta_mod::reverse_complement;
+
+    // A region to pull out of the fasta, 1-based and inclusive
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Extraction {
+        pub scaffold: String,
+        pub start: Option<usize>,
+        pub end: Option<usize>,
+        // The BED name column
+        pub name: Option<String>,
+        pub reverse: bool,
+    }
+
+    impl Extraction {
+        pub fn region(&self) -> String {
+            match (self.start, self.end) {
+                (Some(start), Some(end)) => format!("{}:{}-{}", self.scaffold, start, end),
+                (Some(start), None) => format!("{}:{}", self.scaffold, start),
+                _ => self.scaffold.to_owned(),
+            }
+        }
+
+        pub fn header(&self, template: &str) -> String {
+            //
+            // {region} SCAFFOLD_1:11-20, {scaffold}, {start}, {end},
+            // {strand} + or -, {name} the BED name (or the region)
+            //
+            template
+                .replace("{region}", &self.region())
+                .replace("{scaffold}", &self.scaffold)
+                .replace(
+                    "{start}",
+                    &self.start.map(|x| x.to_string()).unwrap_or_default(),
+                )
+                .replace(
+                    "{end}",
+                    &self.end.map(|x| x.to_string()).unwrap_or_default(),
+                )
+                .replace("{strand}", if self.reverse { "-" } else { "+" })
+                .replace("{name}", self.name.as_ref().unwrap_or(&self.region()))
+        }
+    }
+
+    pub fn parse_extraction(region: &str, index: &fasta::fai::Index) -> Result<Extraction, String> {
+        // The same NAME, NAME:START or NAME:START-END as faidx
+        let parsed = parse_region(region, index)?;
+        let interval = parsed.interval();
+        Ok(Extraction {
+            scaffold: String::from_utf8_lossy(parsed.name()).into_owned(),
+            start: interval.start().map(usize::from),
+            end: interval.end().map(usize::from),
+            name: None,
+            reverse: false,
+        })
+    }
+
+    pub fn read_bed_regions(
+        input: &mut dyn BufRead,
+        use_strand: bool,
+    ) -> Result<Vec<Extraction>, Box<dyn Error>> {
+        //
+        // CHROM START END [NAME SCORE STRAND], START is 0-based
+        // and END exclusive. Header and comment lines are skipped.
+        //
+        let mut regions: Vec<Extraction> = Vec::new();
+        for (index, line) in input.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty()
+                || line.starts_with('#')
+                || line.starts_with("track")
+                || line.starts_with("browser")
+            {
+                continue;
+            }
+            let columns: Vec<&str> = line.split('\t').collect();
+            let coordinates = match columns.as_slice() {
+                [_, start, end, ..] => start
+                    .parse::<usize>()
+                    .ok()
+                    .zip(end.parse::<usize>().ok())
+                    .filter(|(start, end)| start < end),
+                _ => None,
+            };
+            let (start, end) = match coordinates {
+                Some(coordinates) => coordinates,
+                None => {
+                    return Err(format!(
+                        "line {}: expected CHROM<TAB>START<TAB>END with START < END in: {}",
+                        index + 1,
+                        line
+                    )
+                    .into())
+                }
+            };
+            regions.push(Extraction {
+                scaffold: columns[0].to_owned(),
+                start: Some(start + 1),
+                end: Some(end),
+                name: columns
+                    .get(3)
+                    .filter(|x| !x.is_empty() && **x != ".")
+                    .map(|x| x.to_string()),
+                reverse: use_strand && columns.get(5) == Some(&"-"),
+            });
+        }
+        Ok(regions)
+    }
+
+    fn extraction_region(
+        extraction: &Extraction,
+        records: &HashMap<&[u8], &fasta::fai::Record>,
+    ) -> Result<Region, String> {
+        // The BED regions are only checked here, against the index
+        let record = records
+            .get(extraction.scaffold.as_bytes())
+            .ok_or_else(|| format!("{} is not in the fasta", extraction.scaffold))?;
+        let length = record.length() as usize;
+        let start = extraction.start.unwrap_or(1);
+        let end = extraction.end.unwrap_or(length);
+        let outside = || {
+            format!(
+                "{} is outside of {} (1-{})",
+                extraction.region(),
+                extraction.scaffold,
+                length
+            )
+        };
+        if start > end || end > length {
+            return Err(outside());
+        }
+        let start = Position::try_from(start).map_err(|_| outside())?;
+        let end = Position::try_from(end).map_err(|_| outside())?;
+        Ok(Region::new(extraction.scaffold.as_str(), start..=end))
+    }
+
+    pub fn extract_regions<R: BufRead + Seek>(
+        reader: &mut fasta::io::IndexedReader<R>,
+        index: &fasta::fai::Index,
+        regions: &[Extraction],
+        template: &str,
+        line_width: usize,
+        out: &mut dyn Write,
+    ) -> Result<usize, Box<dyn Error>> {
+        // Read one region at a time from the indexed fasta, nothing else is held
+        let records: HashMap<&[u8], &fasta::fai::Record> =
+            index.as_ref().iter().map(|x| (x.name(), x)).collect();
+        let mut writer = fasta::writer::Builder::default()
+            .set_line_base_count(line_width)
+            .build_with_writer(out);
+        for extraction in regions {
+            let region = extraction_region(extraction, &records)?;
+            let record = reader.query(&region)?;
+            let sequence = if extraction.reverse {
+                reverse_complement(record.sequence().as_ref())
+                    .map_err(|e| format!("{} | {}", extraction.region(), e))?
+            } else {
+                record.sequence().as_ref().to_vec()
+            };
+            let definition =
+                fasta::record::Definition::new(extraction.header(template).as_bytes(), None);
+            writer.write_record(&fasta::Record::new(
+                definition,
+                fasta::record::Sequence::from(sequence),
+            ))?;
+        }
+        Ok(regions.len())
+    }
+
+    pub fn extract(arguments: std::option::Option<&ArgMatches>) {
+        let fasta_file: &String = arguments.unwrap().get_one::<String>("fasta-file").unwrap();
+        let reverse: &bool = arguments.unwrap().get_one::<bool>("reverse").unwrap();
+        let use_strand: &bool = arguments.unwrap().get_one::<bool>("strand").unwrap();
+        let line_width: &usize = arguments.unwrap().get_one::<usize>("line_width").unwrap();
+        let template: &String = arguments.unwrap().get_one::<String>("naming").unwrap();
+
+        // The .fai (and .gzi) is built if it is missing or out of date
+        let mut reader = match open_indexed_fasta(fasta_file) {
+            Ok(reader) => reader,
+            Err(e) => panic!("Something is wrong with the file! | {}", e),
+        };
+        let index = reader.index().clone();
+
+        let mut regions: Vec<Extraction> = Vec::new();
+        for region in arguments
+            .unwrap()
+            .get_many::<String>("regions")
+            .into_iter()
+            .flatten()
+        {
+            match parse_extraction(region, &index) {
+                Ok(extraction) => regions.push(extraction),
+                Err(e) => panic!("Something is wrong with the region! | {}", e),
+            }
+        }
+        if let Some(bed) = arguments.unwrap().get_one::<String>("bed") {
+            let mut input = open_reader(bed).expect("Unable to open input");
+            match read_bed_regions(&mut input, *use_strand) {
+                Ok(bed_regions) => regions.extend(bed_regions),
+                Err(e) => panic!("Something is wrong with the BED! | {}", e),
+            }
+        }
+        if *reverse {
+            // Everything is flipped, a - in the BED flips it back
+            for extraction in regions.iter_mut() {
+                extraction.reverse = !extraction.reverse;
+            }
+        }
+        if regions.is_empty() {
+            eprintln!("{}", "NO REGIONS GIVEN, use NAME:START-END or -b".red());
+            std::process::exit(1);
+        }
+
+        let result = match arguments.unwrap().get_one::<String>("output") {
+            Some(output) => {
+                let compression =
+                    output_compression(output, arguments.unwrap().get_one::<String>("compress"));
+                let mut out = FastaOutput::create(output, compression).expect("creation failed");
+                extract_regions(
+                    &mut reader,
+                    &index,
+                    &regions,
+                    template,
+                    *line_width,
+                    &mut out,
+                )
+                .and_then(|count| {
+                    out.finish()?;
+                    println!(
+                        "{}\n{}\n\t{}",
+                        format!("{} REGIONS EXTRACTED", count).green(),
+                        "FOUND HERE:".green(),
+                        output.green()
+                    );
+                    Ok(count)
+                })
+            }
+            None => extract_regions(
+                &mut reader,
+                &index,
+                &regions,
+                template,
+                *line_width,
+                &mut std::io::stdout().lock(),
+            ),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", format!("REGION COULD NOT BE EXTRACTED: {}", e).red());
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+pub mod faidx_mod {
+    use clap::ArgMatches;
+    use colored::Colorize;
+    use noodles::core::Region;
+    use noodles::fasta;
+    use std::error::Error;
+    use std::fs::read_to_string;
+    use std::io::{BufRead, Seek, Write};
+
+    use crate::generics::{open_indexed_fasta, output_compression, FastaOutput};
+
+    pub fn parse_region(region: &str, index: &fasta::fai::Index) -> Result<Region, String> {
+        //
+        // NAME, NAME:START or NAME:START-END like samtools, checked
+        // against the index so a region can't run off the end
+        //
+        let parsed: Region = region
+            .parse()
+            .map_err(|e| format!("{} is not a region | {:?}", region, e))?;
+        let record = index
+            .as_ref()
+            .iter()
+            .find(|x| x.name() == parsed.name())
+            .ok_or_else(|| format!("{} is not in the fasta", parsed.name()))?;
+
+        let interval = parsed.interval();
+        let length = record.length() as usize;
+        let start = interval.start().map(usize::from).unwrap_or(1);
+        let end = interval.end().map(usize::from).unwrap_or(length);
+        if start > end || end > length {
+            return Err(format!(
+                "{} is outside of {} (1-{})",
+                region,
+                parsed.name(),
+                length
+            ));
+        }
+        Ok(parsed)
+    }
+
+    pub fn fetch_regions<R: BufRead + Seek>(
+        reader: &mut fasta::io::IndexedReader<R>,
+        index: &fasta::fai::Index,
+        regions: &[String],
+        line_width: usize,
+        out: &mut dyn Write,
+    ) -> Result<usize, Box<dyn Error>> {
+        // Each region as its own record, named as it was asked for
+        let mut writer = fasta::writer::Builder::default()
+            .set_line_base_count(line_width)
+            .build_with_writer(out);
+        for region in regions {
+            let parsed = parse_region(region, index)?;
+            let record = reader.query(&parsed)?;
+            let definition = fasta::record::Definition::new(region.as_bytes(), None);
+            writer.write_record(&fasta::Record::new(
+                definition,
+                record.sequence().to_owned(),
+            ))?;
+        }
+        Ok(regions.len())
+    }
+
+    pub fn faidx(arguments: std::option::Option<&ArgMatches>) {
+        let fasta_file: &String = arguments.unwrap().get_one::<String>("fasta-file").unwrap();
+        let line_width: &usize = arguments.unwrap().get_one::<usize>("line_width").unwrap();
+
+        // Regions from the command line, then from the file
+        let mut regions: Vec<String> = arguments
+            .unwrap()
+            .get_many::<String>("regions")
+            .map(|x| x.cloned().collect())
+            .unwrap_or_default();
+        if let Some(region_file) = arguments.unwrap().get_one::<String>("region_file") {
+            match read_to_string(region_file) {
+                Ok(contents) => regions.extend(
+                    contents
+                        .lines()
+                        .map(|x| x.trim())
+                        .filter(|x| !x.is_empty() && !x.starts_with('#'))
+                        .map(|x| x.to_string()),
+                ),
+                Err(e) => panic!("Something is wrong with the file! | {}", e),
+            }
+        }
+
+        // Writes the .fai (and .gzi) if it is missing or out of date
+        let mut reader = match open_indexed_fasta(fasta_file) {
+            Ok(reader) => reader,
+            Err(e) => panic!("Something is wrong with the file! | {}", e),
+        };
+        let index = reader.index().clone();
+        // Status goes to stderr, stdout may be the fasta
+        eprintln!(
+            "{}\n\t{}.fai",
+            format!("INDEX OF {} RECORDS", index.as_ref().len()).green(),
+            fasta_file
+        );
+        if regions.is_empty() {
+            return;
+        }
+
+        let result = match arguments.unwrap().get_one::<String>("output") {
+            Some(output) => {
+                let compression =
+                    output_compression(output, arguments.unwrap().get_one::<String>("compress"));
+                let mut out = FastaOutput::create(output, compression).expect("creation failed");
+                fetch_regions(&mut reader, &index, &regions, *line_width, &mut out).and_then(
+                    |count| {
+                        out.finish()?;
+                        println!(
+                            "{}\n{}\n\t{}",
+                            format!("{} REGIONS EXTRACTED", count).green(),
+                            "FOUND HERE:".green(),
+                            output.green()
+                        );
+                        Ok(count)
+                    },
+                )
+            }
+            None => fetch_regions(
+                &mut reader,
+                &index,
+                &regions,
+                *line_width,
+                &mut std::io::stdout().lock(),
+            ),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", format!("REGION COULD NOT BE EXTRACTED: {}", e).red());
+            std::process::exit(1);
+        }
+    }
+}
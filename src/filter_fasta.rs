@@ -1,48 +1,175 @@
 pub mod filter_fasta_mod {
     use clap::ArgMatches;
+    use colored::Colorize;
     use noodles::fasta;
+    use regex::Regex;
+    use std::collections::HashMap;
     use std::error::Error;
+    use std::fmt;
     use std::fs::File;
+    use std::io::Write;
     use std::{
-        fs,
         io::{BufRead, BufReader},
         path::Path,
     };
 
-    fn open_fasta<'a>(
-        exclusions: Vec<&str>,
-        fasta: &'a str,
-        out_file: &str,
-    ) -> std::result::Result<&'a str, Box<dyn Error>> {
-        // Open and read fasta
-        let reader: Result<fasta::Reader<Box<dyn BufRead>>, std::io::Error> =
-            fasta::reader::Builder.build_from_path(fasta);
-
-        // Create new file
-        let file = fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(out_file)?;
-        let mut writer = fasta::Writer::new(file);
-
-        match reader {
-            Ok(fasta) => {
-                // on Ok reading append record to new fasta if
-                // not in user given list of headers
-                let mut binding = fasta;
-                for result in binding.records() {
-                    let record = result?;
-                    let record_name = std::str::from_utf8(record.name())?;
-                    if !exclusions.contains(&record_name) {
-                        writer.write_record(&record)?;
-                    } else {
-                        println!("Found record to exclude: {:?}", &record_name);
-                    }
+    use crate::generics::{output_compression, FastaOutput};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum FilterMode {
+        // Drop what matches
+        Exclude,
+        // Only keep what matches
+        Keep,
+    }
+
+    // What a sequence name is checked against
+    #[derive(Debug, Clone)]
+    pub enum Rule {
+        Name(String),
+        Prefix(String),
+        Pattern(Regex),
+    }
+
+    impl Rule {
+        fn matches(&self, name: &str) -> bool {
+            match self {
+                Rule::Name(x) => x == name,
+                Rule::Prefix(x) => name.starts_with(x.as_str()),
+                Rule::Pattern(x) => x.is_match(name),
+            }
+        }
+    }
+
+    impl fmt::Display for Rule {
+        fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Rule::Name(x) => write!(fmt, "name {}", x),
+                Rule::Prefix(x) => write!(fmt, "prefix {}", x),
+                Rule::Pattern(x) => write!(fmt, "regex {}", x),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct Filter {
+        pub mode: FilterMode,
+        pub rules: Vec<Rule>,
+        // Anything shorter is dropped, whatever the mode
+        pub min_length: Option<usize>,
+    }
+
+    // How many records each rule matched
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct FilterSummary {
+        pub rules: Vec<(String, usize)>,
+        pub too_short: usize,
+        pub kept: usize,
+        pub removed: usize,
+    }
+
+    impl FilterSummary {
+        pub fn unmatched(&self) -> Vec<&String> {
+            self.rules
+                .iter()
+                .filter(|x| x.1 == 0)
+                .map(|x| &x.0)
+                .collect()
+        }
+    }
+
+    impl fmt::Display for FilterSummary {
+        fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(fmt, "FILTER SUMMARY")?;
+            for (rule, count) in &self.rules {
+                writeln!(fmt, "\t{}:\t{}", rule, count)?;
+            }
+            writeln!(fmt, "\ttoo short:\t{}", self.too_short)?;
+            writeln!(fmt, "\tKept:\t\t{}", self.kept)?;
+            writeln!(fmt, "\tRemoved:\t{}", self.removed)
+        }
+    }
+
+    pub fn filter_records(
+        input: &mut dyn BufRead,
+        filter: &Filter,
+        out: &mut dyn Write,
+    ) -> Result<FilterSummary, Box<dyn Error>> {
+        //
+        // Stream the fasta through the filter. Exact names are looked up,
+        // prefixes and regexes are tried in turn. Every rule matching
+        // a record is counted, so a name can count against more than one.
+        //
+        let mut names: HashMap<&str, usize> = HashMap::new();
+        let mut others: Vec<usize> = Vec::new();
+        for (index, rule) in filter.rules.iter().enumerate() {
+            match rule {
+                Rule::Name(name) => {
+                    names.entry(name.as_str()).or_insert(index);
                 }
-                Ok("Removed Exclusionary List")
+                _ => others.push(index),
             }
-            Err(_) => Err("Error: Fasta is not valid check file!".into()),
         }
+
+        let mut counts: Vec<usize> = vec![0; filter.rules.len()];
+        let mut too_short: usize = 0;
+        let (mut kept, mut removed): (usize, usize) = (0, 0);
+
+        let mut reader = fasta::Reader::new(input);
+        let mut writer = fasta::Writer::new(out);
+        for result in reader.records() {
+            let record = result?;
+            let name = std::str::from_utf8(record.name())?;
+
+            let mut matched = false;
+            if let Some(index) = names.get(name) {
+                counts[*index] += 1;
+                matched = true;
+            }
+            for index in &others {
+                if filter.rules[*index].matches(name) {
+                    counts[*index] += 1;
+                    matched = true;
+                }
+            }
+            let short = filter
+                .min_length
+                .is_some_and(|x| record.sequence().len() < x);
+            if short {
+                too_short += 1;
+            }
+
+            let keep = !short
+                && match filter.mode {
+                    FilterMode::Exclude => !matched,
+                    FilterMode::Keep => matched,
+                };
+            if keep {
+                writer.write_record(&record)?;
+                kept += 1;
+            } else {
+                removed += 1;
+            }
+        }
+
+        // A name given twice counts the same for both
+        for (index, rule) in filter.rules.iter().enumerate() {
+            if let Rule::Name(name) = rule {
+                counts[index] = counts[names[name.as_str()]];
+            }
+        }
+
+        Ok(FilterSummary {
+            rules: filter
+                .rules
+                .iter()
+                .map(|x| x.to_string())
+                .zip(counts)
+                .collect(),
+            too_short,
+            kept,
+            removed,
+        })
     }
 
     fn lines_from_file(filename: impl AsRef<Path>) -> Vec<String> {
@@ -50,24 +177,104 @@ pub mod filter_fasta_mod {
         let buf = BufReader::new(file);
         buf.lines()
             .map(|l| l.expect("Could not parse line"))
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
             .collect()
     }
 
     pub fn filter_fasta(arguments: std::option::Option<&ArgMatches>) {
         let fasta = arguments.unwrap().get_one::<String>("fasta").unwrap();
         let outfile = arguments.unwrap().get_one::<String>("output").unwrap();
-        let exclude_list = arguments.unwrap().get_one::<String>("filter_list").unwrap();
-        let exclude_file = arguments.unwrap().get_one::<String>("filter_file").unwrap();
+        let name_list = arguments.unwrap().get_one::<String>("filter_list").unwrap();
+        let name_file = arguments.unwrap().get_one::<String>("filter_file").unwrap();
+        let mode = match arguments
+            .unwrap()
+            .get_one::<String>("mode")
+            .unwrap()
+            .as_str()
+        {
+            "keep" => FilterMode::Keep,
+            _ => FilterMode::Exclude,
+        };
+        let compression =
+            output_compression(outfile, arguments.unwrap().get_one::<String>("compress"));
 
-        if exclude_file != "None" {
-            let exclusion_list = lines_from_file(exclude_file);
-            let exclusion_list_parsed = exclusion_list.iter().map(|p| p.as_str()).collect();
-            let _x = open_fasta(exclusion_list_parsed, fasta, outfile);
+        // Names from the file and list, then prefixes, then regexes
+        let mut rules: Vec<Rule> = Vec::new();
+        if name_file != "None" {
+            rules.extend(lines_from_file(name_file).into_iter().map(Rule::Name));
+        }
+        if name_list != "None" {
+            rules.extend(
+                name_list
+                    .split(',')
+                    .filter(|x| !x.is_empty())
+                    .map(|x| Rule::Name(x.to_string())),
+            );
+        }
+        if let Some(prefixes) = arguments.unwrap().get_one::<String>("prefix") {
+            rules.extend(
+                prefixes
+                    .split(',')
+                    .filter(|x| !x.is_empty())
+                    .map(|x| Rule::Prefix(x.to_string())),
+            );
+        }
+        for pattern in arguments
+            .unwrap()
+            .get_many::<String>("regex")
+            .into_iter()
+            .flatten()
+        {
+            match Regex::new(pattern) {
+                Ok(regex) => rules.push(Rule::Pattern(regex)),
+                Err(e) => panic!("Something is wrong with the regex! | {}", e),
+            }
         }
 
-        if exclude_list != "None" {
-            let list_to_exclude = exclude_list.split(',').collect::<Vec<&str>>();
-            let _y = open_fasta(list_to_exclude, fasta, outfile);
+        let filter = Filter {
+            mode,
+            rules,
+            min_length: arguments.unwrap().get_one::<usize>("min_length").copied(),
+        };
+        if filter.rules.is_empty() && filter.min_length.is_none() {
+            eprintln!(
+                "{}",
+                "NOTHING TO FILTER BY, give names (-l/-f), prefixes (-x), regexes (-e) or a minimum length (-n)"
+                    .red()
+            );
+            std::process::exit(1);
+        }
+        if filter.rules.is_empty() && mode == FilterMode::Keep {
+            eprintln!(
+                "{}",
+                "KEEP MODE NEEDS NAMES, PREFIXES OR REGEXES TO KEEP".red()
+            );
+            std::process::exit(1);
+        }
+
+        let mut input = match crate::generics::open_reader(fasta) {
+            Ok(input) => input,
+            Err(e) => panic!("Something is wrong with the file! | {}", e),
+        };
+        let mut file = FastaOutput::create(outfile, compression).expect("creation failed");
+        let summary = match filter_records(&mut input, &filter, &mut file) {
+            Ok(summary) => summary,
+            Err(e) => panic!("Error: Fasta is not valid check file! | {}", e),
+        };
+        file.finish().expect("Unable to write to file");
+
+        print!("{}", summary);
+        let unmatched = summary.unmatched();
+        if !unmatched.is_empty() {
+            println!(
+                "{}",
+                format!("WARNING: {} MATCHED NOTHING:", unmatched.len()).yellow()
+            );
+            for rule in unmatched {
+                println!("\t{}", rule.yellow());
+            }
         }
+        println!("{}\n\t{}", "FOUND HERE:".green(), outfile.green());
     }
 }
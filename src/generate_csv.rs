@@ -2,14 +2,35 @@
 /// This is for data tracking for TreeVal
 /// This may be replaced or enhanced with a function to send this to a Google Sheets so the team has an easier way of tracking it all.
 pub mod gencsv_mod {
-    use crate::generics::get_folder_list;
     use clap::ArgMatches;
     use csv::Writer;
+    use noodles::fasta;
     use std::collections::HashMap;
     use std::error::Error;
+    use std::io::BufRead;
     use std::{fs, path::Path, path::PathBuf};
     use walkdir::WalkDir;
 
+    // IUPAC nucleotide codes plus gap
+    const NUCLEOTIDES: &[u8] = b"ACGTUNRYKMSWBDHV-";
+    // IUPAC amino acid codes plus stop and gap
+    const AMINO_ACIDS: &[u8] = b"ACDEFGHIKLMNPQRSTVWYBZJUOX*-";
+
+    fn get_folder_list(root: &str) -> Vec<PathBuf> {
+        // Return the directories directly inside root, sorted so
+        // that the output is the same on every run
+        let mut folders: Vec<PathBuf> = match fs::read_dir(root) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .collect(),
+            Err(e) => panic!("Unable to read directory: {} | {}", root, e),
+        };
+        folders.sort();
+        folders
+    }
+
     fn get_file_list(root: &str) -> Vec<PathBuf> {
         WalkDir::new(root)
             .into_iter()
@@ -19,6 +40,49 @@ pub mod gencsv_mod {
             .collect()
     }
 
+    pub fn check_data_type(path: &Path, data_type: &str) -> Result<usize, Box<dyn Error>> {
+        //
+        // Check that a file is a readable fasta whose sequences fit the
+        // alphabet of the data type folder it is in, returns the record count.
+        // pep must be protein, cds/cdna/rna must be nucleotide, anything
+        // else only needs to be a valid fasta.
+        //
+        let mut reader: fasta::Reader<Box<dyn BufRead>> = crate::generics::open_fasta(path)?;
+
+        let mut records: usize = 0;
+        let mut only_acgtn = true;
+        for result in reader.records() {
+            let record = result?;
+            records += 1;
+            for base in record.sequence().as_ref() {
+                let base = base.to_ascii_uppercase();
+                let allowed = match data_type {
+                    "pep" => AMINO_ACIDS.contains(&base),
+                    "cds" | "cdna" | "rna" => NUCLEOTIDES.contains(&base),
+                    _ => true,
+                };
+                if !allowed {
+                    return Err(format!(
+                        "{} in record {} is not a valid {} character",
+                        base as char,
+                        String::from_utf8_lossy(record.name()),
+                        data_type
+                    )
+                    .into());
+                }
+                only_acgtn &= b"ACGTUN".contains(&base);
+            }
+        }
+
+        if records == 0 {
+            return Err("No header/sequence pairs found".into());
+        }
+        if data_type == "pep" && only_acgtn {
+            return Err("Sequences look like nucleotide, not pep".into());
+        }
+        Ok(records)
+    }
+
     // Function to convert list to dictionary
     fn list_2_dict(file_list: &Vec<PathBuf>) -> (HashMap<String, Vec<String>>, String) {
         let mut file_dict = HashMap::new();
@@ -28,11 +92,18 @@ pub mod gencsv_mod {
             let path_list: Vec<&str> = path_str.split('/').collect();
             let file_name = path_list[path_list.len() - 1];
             if file_name.to_lowercase() != "readme.txt" && file_name.to_lowercase() != "readme" {
+                // Only list files that are usable for their data type
+                let data_type = path_list[path_list.len() - 2];
+                if let Err(e) = check_data_type(path, data_type) {
+                    println!("SKIPPING: {} | {}", path_str, e);
+                    continue;
+                }
+
                 file_dict.insert(
                     file_name.to_string(),
                     vec![
                         path_list[path_list.len() - 3].to_string(),
-                        path_list[path_list.len() - 2].to_string(),
+                        data_type.to_string(),
                         path_str.to_string(),
                     ],
                 );
@@ -70,7 +141,7 @@ pub mod gencsv_mod {
         println!("{}", save_dir);
 
         let mut wtr = Writer::from_path(save_path)?;
-        wtr.write_record(&["org", "type", "data_file"])?;
+        wtr.write_record(["org", "type", "data_file"])?;
         for (_key, value) in dict_of_data {
             wtr.write_record(&value)?;
         }
@@ -80,8 +151,20 @@ pub mod gencsv_mod {
 
     pub fn gencsv(arguments: std::option::Option<&ArgMatches>) {
         let geneset_folder: &String = arguments.unwrap().get_one::<String>("geneset_dir").unwrap();
+        let clade: &String = arguments
+            .unwrap()
+            .get_one::<String>("specifiy_clade")
+            .unwrap();
+
+        // Either every clade or only the one asked for
+        let clade_folder: Vec<PathBuf> = get_folder_list(geneset_folder)
+            .into_iter()
+            .filter(|x| clade == "ALL" || x.file_name().unwrap().to_str() == Some(clade))
+            .collect();
 
-        let clade_folder = get_folder_list(&geneset_folder);
+        if clade_folder.is_empty() {
+            panic!("No clade folder called {} in {}", clade, geneset_folder)
+        }
 
         for clade in clade_folder {
             let save_clade = clade.clone();
@@ -94,16 +177,15 @@ pub mod gencsv_mod {
                 .collect();
 
             for org in new_org_folder {
-                let mut master_list = Vec::new();
-
                 let accession_folder = get_folder_list(
-                    &<PathBuf as Clone>::clone(&org)
+                    &<PathBuf as Clone>::clone(org)
                         .into_os_string()
                         .into_string()
                         .unwrap(),
                 );
 
                 for accession in accession_folder {
+                    let mut master_list = Vec::new();
                     let data_list = get_folder_list(accession.to_str().unwrap());
                     for data in data_list {
                         master_list.push(get_file_list(data.to_str().unwrap()));
@@ -113,6 +195,10 @@ pub mod gencsv_mod {
                     let orgs: String;
                     (file_dict, orgs) =
                         list_2_dict(&master_list.iter().flatten().cloned().collect());
+                    if file_dict.is_empty() {
+                        println!("No valid data files found in: {}", accession.display());
+                        continue;
+                    }
                     let save_loc = format!(
                         "{}/{}",
                         geneset_folder,
@@ -1,7 +1,13 @@
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use md5::{Digest, Md5};
+use noodles::bgzf;
 use noodles::fasta;
 use noodles::fasta::record::Definition;
 use std::error::Error;
-use std::fs::{self, File, OpenOptions};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
 use std::{collections::HashMap, fmt, io::BufRead, result, str};
 
 #[derive(Debug, Clone)]
@@ -15,33 +21,599 @@ impl fmt::Display for EmptyVec {
     }
 }
 
-pub fn validate_fasta(
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bgzf,
+}
+
+impl str::FromStr for Compression {
+    type Err = String;
+
+    fn from_str(value: &str) -> result::Result<Compression, String> {
+        match value {
+            "none" => Ok(Compression::None),
+            "gzip" => Ok(Compression::Gzip),
+            "bgzip" => Ok(Compression::Bgzf),
+            _ => Err(format!(
+                "'{}' is not a compression, use none, gzip or bgzip",
+                value
+            )),
+        }
+    }
+}
+
+impl Compression {
+    pub fn suffix(&self) -> &str {
+        // Added to the generated file names
+        match self {
+            Compression::None => "",
+            Compression::Gzip | Compression::Bgzf => ".gz",
+        }
+    }
+}
+
+pub fn output_compression(path: &str, flag: Option<&String>) -> Compression {
+    // The flag wins, otherwise .gz/.bgz outputs are bgzipped so
+    // that they can be indexed, anything else is plain text
+    match flag {
+        Some(value) => value.parse().unwrap_or_else(|e| panic!("{}", e)),
+        None if path.ends_with(".gz") || path.ends_with(".bgz") => Compression::Bgzf,
+        None => Compression::None,
+    }
+}
+
+pub fn detect_compression<P: AsRef<Path>>(path: P) -> io::Result<Compression> {
+    // Going by the magic bytes rather than the extension
+    // gzip starts 1f 8b, BGZF is gzip with a BC extra field
+    let mut header = [0u8; 18];
+    let mut file = File::open(path)?;
+    let mut read = 0;
+    while read < header.len() {
+        match file.read(&mut header[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+
+    if read < 2 || header[0..2] != [0x1f, 0x8b] {
+        Ok(Compression::None)
+    } else if read >= 14 && header[3] & 0x04 != 0 && header[12..14] == *b"BC" {
+        Ok(Compression::Bgzf)
+    } else {
+        Ok(Compression::Gzip)
+    }
+}
+
+fn open_decoded<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn Read>> {
+    // A plain, gzip or BGZF file read from the start as plain text
+    // BGZF is a series of gzip members so one decoder does for both
+    let file = File::open(&path)?;
+    match detect_compression(&path)? {
+        Compression::None => Ok(Box::new(file)),
+        Compression::Gzip | Compression::Bgzf => Ok(Box::new(MultiGzDecoder::new(file))),
+    }
+}
+
+pub fn open_reader<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn BufRead>> {
+    Ok(Box::new(BufReader::new(open_decoded(path)?)))
+}
+
+pub struct Md5Tee<T> {
+    // Passes reads or writes straight through, keeping the md5 of the bytes
+    inner: T,
+    hasher: Md5,
+}
+
+impl<T> Md5Tee<T> {
+    pub fn new(inner: T) -> Md5Tee<T> {
+        Md5Tee {
+            inner,
+            hasher: Md5::new(),
+        }
+    }
+
+    pub fn md5(&self) -> String {
+        format!("{:x}", self.hasher.clone().finalize())
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for Md5Tee<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<W: Write> Write for Md5Tee<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub fn open_md5_reader<P: AsRef<Path>>(path: P) -> io::Result<BufReader<Md5Tee<Box<dyn Read>>>> {
+    // As open_reader, with the md5 of what has been read, the same as md5_of
+    // once the whole file has been read
+    Ok(BufReader::new(Md5Tee::new(open_decoded(path)?)))
+}
+
+pub fn open_fasta<P: AsRef<Path>>(path: P) -> io::Result<fasta::Reader<Box<dyn BufRead>>> {
+    fasta::reader::Builder.build_from_reader(open_reader(path)?)
+}
+
+pub fn md5_of<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    // Of the uncompressed contents, so a fasta can be (re)compressed
+    // and still have the same checksum
+    let mut hasher = Md5::new();
+    io::copy(&mut open_decoded(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub fn open_indexed_fasta<P: AsRef<Path>>(
+    path: P,
+) -> io::Result<fasta::io::IndexedReader<fasta::io::BufReader<File>>> {
+    //
+    // Random access needs the .fai, and for BGZF the .gzi as well,
+    // both are built if they are missing. Plain gzip can't be read this way
+    //
+    let index = ensure_fai(&path)?;
+    open_fasta_with_index(path, index)
+}
+
+pub fn open_fasta_with_index<P: AsRef<Path>>(
+    path: P,
+    index: fasta::fai::Index,
+) -> io::Result<fasta::io::IndexedReader<fasta::io::BufReader<File>>> {
+    // As open_indexed_fasta, with an index already checked by ensure_fai
+    let path = path.as_ref();
+    let reader = match detect_compression(path)? {
+        Compression::None => fasta::io::BufReader::Uncompressed(BufReader::new(File::open(path)?)),
+        Compression::Bgzf => fasta::io::BufReader::Bgzf(
+            bgzf::indexed_reader::Builder::default()
+                .build_from_path(path)
+                .map_err(|e| {
+                    io::Error::new(
+                        e.kind(),
+                        format!("{} needs a .gzi index (bgzip -r) | {}", path.display(), e),
+                    )
+                })?,
+        ),
+        Compression::Gzip => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} is gzip compressed, it needs to be bgzip compressed for indexed access",
+                    path.display()
+                ),
+            ))
+        }
+    };
+    fasta::io::indexed_reader::Builder::default()
+        .set_index(index)
+        .build_from_reader(reader)
+}
+
+fn write_gzi(path: &str, blocks: &[(u64, u64)]) -> io::Result<()> {
+    // Count, then (compressed, uncompressed) offsets of each block after the first
+    let mut gzi = BufWriter::new(File::create(path)?);
+    gzi.write_all(&(blocks.len() as u64).to_le_bytes())?;
+    for (compressed, uncompressed) in blocks {
+        gzi.write_all(&compressed.to_le_bytes())?;
+        gzi.write_all(&uncompressed.to_le_bytes())?;
+    }
+    gzi.flush()
+}
+
+pub fn bgzf_blocks<P: AsRef<Path>>(path: P) -> io::Result<Vec<(u64, u64)>> {
+    //
+    // Where each BGZF block starts, for the .gzi. Every block header
+    // gives its compressed size (BSIZE) and the block ends with the
+    // size of its uncompressed data (ISIZE)
+    //
+    let mut reader = BufReader::new(File::open(&path)?);
+    let mut blocks: Vec<(u64, u64)> = Vec::new();
+    let (mut compressed, mut uncompressed): (u64, u64) = (0, 0);
+    let mut header = [0u8; 18];
+    let mut trailer = [0u8; 4];
+    while !reader.fill_buf()?.is_empty() {
+        reader.read_exact(&mut header)?;
+        if header[0..2] != [0x1f, 0x8b] || header[12..14] != *b"BC" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} is not BGZF, block at {} has no BC header",
+                    path.as_ref().display(),
+                    compressed
+                ),
+            ));
+        }
+        let block_size = u16::from_le_bytes([header[16], header[17]]) as u64 + 1;
+        io::copy(
+            &mut (&mut reader).take(block_size - 18 - 4),
+            &mut io::sink(),
+        )?;
+        reader.read_exact(&mut trailer)?;
+        let data_size = u32::from_le_bytes(trailer) as u64;
+
+        if compressed > 0 && data_size > 0 {
+            blocks.push((compressed, uncompressed));
+        }
+        compressed += block_size;
+        uncompressed += data_size;
+    }
+    Ok(blocks)
+}
+
+pub fn build_fai<P: AsRef<Path>>(path: P) -> io::Result<fasta::fai::Index> {
+    // Offsets are always into the uncompressed text, BGZF or not
+    let mut indexer = fasta::io::Indexer::new(open_reader(path)?);
+    let mut records = Vec::new();
+    while let Some(record) = indexer.index_record()? {
+        records.push(record);
+    }
+    Ok(fasta::fai::Index::from(records))
+}
+
+fn record_end(record: &fasta::fai::Record) -> u64 {
+    // The byte after the last line of the sequence (and its newline)
+    let (length, bases, width) = (record.length(), record.line_bases(), record.line_width());
+    if length == 0 || bases == 0 {
+        return record.offset();
+    }
+    let remainder = length % bases;
+    let partial = if remainder > 0 {
+        remainder + width - bases
+    } else {
+        0
+    };
+    record.offset() + (length / bases) * width + partial
+}
+
+pub fn check_fai<P: AsRef<Path>>(
+    reader: &mut fasta::io::BufReader<File>,
+    path: P,
+    index: &fasta::fai::Index,
+) -> result::Result<(), String> {
+    //
+    // Does the .fai line up with the fasta? Each record should start with
+    // its header right where the previous one ended, and the sequence start
+    // at its offset. The last should end at the end of the file.
+    //
+    let path = path.as_ref().display();
+    let mut position: u64 = 0;
+    let mut line: Vec<u8> = Vec::new();
+    for record in index.as_ref() {
+        let name = String::from_utf8_lossy(record.name()).to_string();
+        line.clear();
+        reader
+            .seek(io::SeekFrom::Start(position))
+            .and_then(|_| reader.read_until(b'\n', &mut line))
+            .map_err(|e| format!("{} can't be read at {} | {}", path, position, e))?;
+
+        let header = line.strip_prefix(b">").unwrap_or_default();
+        let header_name = header
+            .split(|x| x.is_ascii_whitespace())
+            .next()
+            .unwrap_or_default();
+        if header_name != name.as_bytes() {
+            return Err(format!("{} is not at {} of {}", name, position, path));
+        }
+        if position + line.len() as u64 != record.offset() {
+            return Err(format!(
+                "the sequence of {} starts at {} not {}",
+                name,
+                position + line.len() as u64,
+                record.offset()
+            ));
+        }
+        position = record_end(record);
+    }
+
+    line.clear();
+    reader
+        .seek(io::SeekFrom::Start(position))
+        .and_then(|_| reader.read_until(b'\n', &mut line))
+        .map_err(|e| format!("{} can't be read at {} | {}", path, position, e))?;
+    if line.iter().any(|x| !x.is_ascii_whitespace()) {
+        return Err(format!(
+            "{} carries on past the last record of the index",
+            path
+        ));
+    }
+    Ok(())
+}
+
+pub fn ensure_fai<P: AsRef<Path>>(path: P) -> io::Result<fasta::fai::Index> {
+    //
+    // The .fai (and .gzi if bgzipped) next to the fasta, built if it is
+    // missing, malformed or doesn't line up with the fasta.
+    // If it can't be written out it is still used from memory.
+    //
+    let path = path.as_ref();
+    let fai_path = format!("{}.fai", path.display());
+    let gzi_path = format!("{}.gzi", path.display());
+
+    let compression = detect_compression(path)?;
+    if compression == Compression::Gzip {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} is gzip compressed, it needs to be bgzip compressed for indexed access",
+                path.display()
+            ),
+        ));
+    }
+    if compression == Compression::Bgzf && !Path::new(&gzi_path).exists() {
+        eprintln!("BUILDING MISSING GZI: {}", gzi_path);
+        write_gzi(&gzi_path, &bgzf_blocks(path)?)?;
+    }
+
+    let mut reader = match compression {
+        Compression::Bgzf => fasta::io::BufReader::Bgzf(
+            bgzf::indexed_reader::Builder::default().build_from_path(path)?,
+        ),
+        _ => fasta::io::BufReader::Uncompressed(BufReader::new(File::open(path)?)),
+    };
+    let problem = match fasta::fai::read(&fai_path) {
+        Ok(index) => match check_fai(&mut reader, path, &index) {
+            Ok(()) => return Ok(index),
+            Err(e) => e,
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => "missing".to_string(),
+        Err(e) => format!("malformed, {}", e),
+    };
+
+    eprintln!("BUILDING FAI: {} | {}", fai_path, problem);
+    let index = build_fai(path)?;
+    let written = File::create(&fai_path)
+        .and_then(|file| fasta::fai::Writer::new(BufWriter::new(file)).write_index(&index));
+    if let Err(e) = written {
+        eprintln!(
+            "Unable to write {}, using the index from memory | {}",
+            fai_path, e
+        );
+    }
+    Ok(index)
+}
+
+enum OutputStream {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Bgzf(bgzf::Writer<BufWriter<File>>),
+}
+
+#[derive(Default)]
+struct FaiIndexer {
+    // Builds the .fai from the bytes as they are written
+    records: Vec<(String, u64, u64, u64, u64)>,
+    name: Vec<u8>,
+    in_header: bool,
+    at_line_start: bool,
+    length: u64,
+    offset: u64,
+    line_bases: u64,
+    line_width: u64,
+    current_bases: u64,
+    current_width: u64,
+    position: u64,
+}
+
+impl FaiIndexer {
+    fn finish_record(&mut self) {
+        if self.name.is_empty() {
+            return;
+        }
+        let name = String::from_utf8_lossy(&self.name);
+        let name = name
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        if self.line_bases == 0 {
+            // A single unterminated line, or no sequence at all
+            self.line_bases = self.current_bases;
+            self.line_width = self.current_width;
+        }
+        self.records.push((
+            name,
+            self.length,
+            self.offset,
+            self.line_bases,
+            self.line_width,
+        ));
+        self.name.clear();
+        self.length = 0;
+        self.line_bases = 0;
+        self.line_width = 0;
+        self.current_bases = 0;
+        self.current_width = 0;
+    }
+
+    fn update(&mut self, buf: &[u8]) {
+        for &byte in buf {
+            if byte == b'>' && (self.at_line_start || self.position == 0) {
+                self.finish_record();
+                self.in_header = true;
+            } else if self.in_header {
+                if byte == b'\n' {
+                    self.in_header = false;
+                    self.offset = self.position + 1;
+                } else {
+                    self.name.push(byte);
+                }
+            } else if byte == b'\n' {
+                self.current_width += 1;
+                if self.line_bases == 0 {
+                    self.line_bases = self.current_bases;
+                    self.line_width = self.current_width;
+                }
+                self.current_bases = 0;
+                self.current_width = 0;
+            } else {
+                self.current_width += 1;
+                if byte != b'\r' {
+                    self.current_bases += 1;
+                    self.length += 1;
+                }
+            }
+            self.at_line_start = byte == b'\n';
+            self.position += 1;
+        }
+    }
+}
+
+pub struct FastaOutput {
+    //
+    // Where every fasta is written, plain text, gzip or BGZF.
+    // BGZF also gets a .fai and .gzi written next to it on finish
+    // so that it can go straight back into curate or samtools
+    //
+    path: String,
+    stream: OutputStream,
+    indexer: FaiIndexer,
+    written: u64,
+    blocks: Vec<(u64, u64)>,
+}
+
+impl FastaOutput {
+    pub fn create(path: &str, compression: Compression) -> io::Result<FastaOutput> {
+        let file = BufWriter::new(File::create(path)?);
+        let stream = match compression {
+            Compression::None => OutputStream::Plain(file),
+            Compression::Gzip => {
+                OutputStream::Gzip(GzEncoder::new(file, flate2::Compression::default()))
+            }
+            Compression::Bgzf => OutputStream::Bgzf(bgzf::Writer::new(file)),
+        };
+        Ok(FastaOutput {
+            path: path.to_owned(),
+            stream,
+            indexer: FaiIndexer::default(),
+            written: 0,
+            blocks: Vec::new(),
+        })
+    }
+
+    fn track_block(&mut self, before: u64) {
+        // A new BGZF block starts wherever the compressed position moved
+        if let OutputStream::Bgzf(writer) = &self.stream {
+            if writer.position() != before {
+                self.blocks.push((writer.position(), self.written));
+            }
+        }
+    }
+
+    fn bgzf_position(&self) -> u64 {
+        match &self.stream {
+            OutputStream::Bgzf(writer) => writer.position(),
+            _ => 0,
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.stream {
+            OutputStream::Plain(mut writer) => writer.flush(),
+            OutputStream::Gzip(writer) => writer.finish()?.flush(),
+            OutputStream::Bgzf(writer) => {
+                writer.finish()?.flush()?;
+
+                self.indexer.finish_record();
+                let mut fai = BufWriter::new(File::create(format!("{}.fai", self.path))?);
+                for (name, length, offset, line_bases, line_width) in &self.indexer.records {
+                    writeln!(
+                        fai,
+                        "{}\t{}\t{}\t{}\t{}",
+                        name, length, offset, line_bases, line_width
+                    )?;
+                }
+                fai.flush()?;
+
+                // The end of the last block isn't the start of another
+                if self.blocks.last().map(|x| x.1) == Some(self.written) {
+                    self.blocks.pop();
+                }
+                write_gzi(&format!("{}.gzi", self.path), &self.blocks)
+            }
+        }
+    }
+}
+
+impl Write for FastaOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let before = self.bgzf_position();
+        let written = match &mut self.stream {
+            OutputStream::Plain(writer) => writer.write(buf)?,
+            OutputStream::Gzip(writer) => writer.write(buf)?,
+            OutputStream::Bgzf(writer) => writer.write(buf)?,
+        };
+        if let OutputStream::Bgzf(_) = self.stream {
+            self.indexer.update(&buf[..written]);
+        }
+        self.written += written as u64;
+        self.track_block(before);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let before = self.bgzf_position();
+        match &mut self.stream {
+            OutputStream::Plain(writer) => writer.flush()?,
+            OutputStream::Gzip(writer) => writer.flush()?,
+            OutputStream::Bgzf(writer) => writer.flush()?,
+        };
+        self.track_block(before);
+        Ok(())
+    }
+}
+
+pub fn map_records<T>(
     path: &str,
-) -> result::Result<HashMap<std::string::String, usize>, Box<dyn Error>> {
-    // Simply validate the fasta is valid by reading though and ensure there are
-    // valid record formats through out the file
-    // Return a Dict of header and length
-    let reader: Result<fasta::Reader<Box<dyn BufRead>>, std::io::Error> =
-        fasta::reader::Builder.build_from_path(path);
-    let mut fasta_map = HashMap::new();
-
-    match &reader {
-        Ok(_fasta) => {
-            let mut binding: fasta::Reader<Box<dyn BufRead>> =
-                reader.expect("NO VALID HEADER / SEQUENCE PAIRS");
+    mut f: impl FnMut(&str, &[u8]) -> T,
+) -> result::Result<Vec<T>, Box<dyn Error>> {
+    // Read though the fasta, ensuring there are valid record formats
+    // through out the file, and return f of each name and sequence in order
+    let reader: Result<fasta::Reader<Box<dyn BufRead>>, std::io::Error> = open_fasta(path);
+
+    match reader {
+        Ok(mut binding) => {
+            let mut mapped = Vec::new();
             for result in binding.records() {
                 let record = result?;
-                fasta_map.insert(
-                    str::from_utf8(record.name())?.to_string(),
-                    record.sequence().len(),
-                );
+                mapped.push(f(
+                    str::from_utf8(record.name())?,
+                    record.sequence().as_ref(),
+                ));
             }
-            Ok(fasta_map)
+            Ok(mapped)
         }
         Err(_) => Err("Error: Fasta is not valid check file!".into()),
     }
 }
 
+pub fn validate_fasta(
+    path: &str,
+) -> result::Result<HashMap<std::string::String, usize>, Box<dyn Error>> {
+    // Simply validate the fasta is valid by reading though it
+    // Return a Dict of header and length
+    Ok(
+        map_records(path, |name, sequence| (name.to_string(), sequence.len()))?
+            .into_iter()
+            .collect(),
+    )
+}
+
 #[allow(clippy::iter_kv_map)]
 pub fn only_keys<K, V>(map: HashMap<K, V>) -> impl Iterator<Item = K> {
     // Take a HashMap and return a Key only Vec
@@ -95,21 +667,16 @@ pub fn write_fasta(
     outdir: &String,
     file_name: String,
     fasta_record: Vec<noodles::fasta::Record>,
+    compression: Compression,
 ) -> std::io::Result<()> {
-    // Create file
+    // Create file, .gz is added to the name if compressed
     fs::create_dir_all(outdir)?;
-    let file_path = format!("{}/{}", outdir, file_name);
-    let _data_file = File::create(&file_path);
+    let file_path = format!("{}/{}{}", outdir, file_name, compression.suffix());
+    let mut file = FastaOutput::create(&file_path, compression)?;
 
-    // Append to file
-    let file = OpenOptions::new()
-        .append(true)
-        .open(file_path)
-        .expect("creation failed");
-
-    let mut writer = fasta::Writer::new(file);
+    let mut writer = fasta::Writer::new(&mut file);
     for i in fasta_record {
-        writer.write_record(&i).unwrap();
+        writer.write_record(&i)?;
     }
-    Ok(())
+    file.finish()
 }
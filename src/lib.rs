@@ -1,4 +1,30 @@
+pub mod naming;
+pub use naming::*;
 pub mod tpf_fasta;
 pub use tpf_fasta::*;
+pub mod curation_report;
+pub use curation_report::*;
+pub mod liftover;
+pub use liftover::*;
+pub mod faidx;
+pub use faidx::*;
+pub mod extract;
+pub use extract::*;
+pub mod profile;
+pub use profile::*;
+pub mod subset;
+pub use subset::*;
+pub mod map_headers;
+pub use map_headers::*;
+pub mod remap_head;
+pub use remap_head::*;
+pub mod remap_file;
+pub use remap_file::*;
+pub mod merge_haps;
+pub use merge_haps::*;
+pub mod filter_fasta;
+pub use filter_fasta::*;
+pub mod generate_csv;
+pub use generate_csv::*;
 mod generics;
 pub use generics::*;
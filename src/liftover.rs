@@ -0,0 +1,492 @@
+pub mod liftover_mod {
+    use clap::ArgMatches;
+    use colored::Colorize;
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::fs::File;
+    use std::io::{BufRead, BufWriter, Write};
+    use std::path::Path;
+
+    use crate::generics::open_reader;
+    use crate::tpf_fasta::tpf_fasta_mod::{
+        apply_naming, components, gapped_tpf, group_by_scaffold, is_agp, naming_scheme,
+        read_layout, TpfEntry,
+    };
+
+    // Where a component of the original assembly ended up
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Placement {
+        pub ori_start: usize,
+        pub ori_end: usize,
+        pub new_scaffold: String,
+        pub new_start: usize,
+        pub minus: bool,
+    }
+
+    impl Placement {
+        fn map(&self, position: usize) -> usize {
+            // 1-based position in the original to 1-based in the curated
+            if self.minus {
+                self.new_start + (self.ori_end - position)
+            } else {
+                self.new_start + (position - self.ori_start)
+            }
+        }
+    }
+
+    // A lifted interval, 1-based and inclusive, flipped if it is now
+    // on the other strand
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Lifted {
+        pub scaffold: String,
+        pub start: usize,
+        pub end: usize,
+        pub flipped: bool,
+    }
+
+    #[derive(Debug, Default)]
+    pub struct LiftMap {
+        placements: HashMap<String, Vec<Placement>>,
+        pub scaffold_lengths: HashMap<String, usize>,
+    }
+
+    impl LiftMap {
+        pub fn new(entries: &[TpfEntry], n_length: usize) -> LiftMap {
+            //
+            // Walk the layout in the same way curate writes it, keeping
+            // where each component starts in its new scaffold
+            //
+            let mut lift_map = LiftMap::default();
+            for (scaffold, scaffold_entries) in group_by_scaffold(entries) {
+                let mut offset: usize = 0;
+                for entry in scaffold_entries {
+                    if let TpfEntry::Component(tpf) = entry {
+                        lift_map
+                            .placements
+                            .entry(tpf.ori_scaffold.to_owned())
+                            .or_default()
+                            .push(Placement {
+                                ori_start: tpf.start_coord,
+                                ori_end: tpf.end_coord,
+                                new_scaffold: scaffold.to_owned(),
+                                new_start: offset + 1,
+                                minus: tpf.orientation == "MINUS",
+                            });
+                    }
+                    offset += entry.length(n_length);
+                }
+                lift_map.scaffold_lengths.insert(scaffold, offset);
+            }
+            for placements in lift_map.placements.values_mut() {
+                placements.sort_by_key(|x| (x.ori_start, x.ori_end));
+            }
+            lift_map
+        }
+
+        pub fn lift(&self, scaffold: &str, start: usize, end: usize) -> Result<Lifted, String> {
+            //
+            // Lift a 1-based inclusive interval. Each base of it has to be
+            // in the layout, and it has to land in one piece, so features
+            // crossing a break (or a gap added in curation) can't be lifted
+            //
+            let placements = self
+                .placements
+                .get(scaffold)
+                .ok_or_else(|| format!("{} is not in the layout", scaffold))?;
+
+            let mut pieces: Vec<Lifted> = Vec::new();
+            let mut position = start;
+            for placement in placements {
+                if placement.ori_end < position || placement.ori_start > end {
+                    continue;
+                }
+                if placement.ori_start > position {
+                    break;
+                }
+                let piece_end = placement.ori_end.min(end);
+                let (a, b) = (placement.map(position), placement.map(piece_end));
+                pieces.push(Lifted {
+                    scaffold: placement.new_scaffold.to_owned(),
+                    start: a.min(b),
+                    end: a.max(b),
+                    flipped: placement.minus,
+                });
+                position = piece_end + 1;
+                if position > end {
+                    break;
+                }
+            }
+            if position <= end {
+                return Err(format!(
+                    "{}:{}-{} was dropped in curation",
+                    scaffold, position, end
+                ));
+            }
+
+            // Pieces which carry straight on from each other are still one
+            let mut merged: Vec<Lifted> = Vec::new();
+            for piece in pieces {
+                match merged.last_mut() {
+                    Some(last)
+                        if last.scaffold == piece.scaffold
+                            && last.flipped == piece.flipped
+                            && (!piece.flipped && piece.start == last.end + 1
+                                || piece.flipped && piece.end + 1 == last.start) =>
+                    {
+                        last.start = last.start.min(piece.start);
+                        last.end = last.end.max(piece.end);
+                    }
+                    _ => merged.push(piece),
+                }
+            }
+
+            if merged.len() > 1 {
+                return Err(format!(
+                    "spans a break, lands in {}",
+                    merged
+                        .iter()
+                        .map(|x| format!("{}:{}-{}", x.scaffold, x.start, x.end))
+                        .collect::<Vec<String>>()
+                        .join(" | ")
+                ));
+            }
+            Ok(merged.remove(0))
+        }
+    }
+
+    // Which columns of a feature file hold the interval
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Columns {
+        pub name: usize,
+        pub start: usize,
+        pub end: usize,
+        pub strand: Option<usize>,
+        // Scaffold length, PAF only
+        pub length: Option<usize>,
+        // BED and PAF are 0-based half open, GFF3 and BUSCO 1-based
+        pub zero_based: bool,
+    }
+
+    pub fn feature_columns(format: &str, paf_side: &str) -> Result<Columns, Box<dyn Error>> {
+        let columns = match (format, paf_side) {
+            ("bed", _) => Columns {
+                name: 0,
+                start: 1,
+                end: 2,
+                strand: Some(5),
+                length: None,
+                zero_based: true,
+            },
+            ("gff3", _) => Columns {
+                name: 0,
+                start: 3,
+                end: 4,
+                strand: Some(6),
+                length: None,
+                zero_based: false,
+            },
+            ("busco", _) => Columns {
+                name: 2,
+                start: 3,
+                end: 4,
+                strand: Some(5),
+                length: None,
+                zero_based: false,
+            },
+            ("paf", "query") => Columns {
+                name: 0,
+                start: 2,
+                end: 3,
+                strand: Some(4),
+                length: Some(1),
+                zero_based: true,
+            },
+            ("paf", _) => Columns {
+                name: 5,
+                start: 7,
+                end: 8,
+                strand: Some(4),
+                length: Some(6),
+                zero_based: true,
+            },
+            _ => return Err(format!("{} is not a format liftover knows", format).into()),
+        };
+        Ok(columns)
+    }
+
+    pub fn detect_format(path: &str) -> Option<String> {
+        // From the extension, ignoring a .gz
+        let lower = path.to_lowercase();
+        let lower = lower.trim_end_matches(".gz");
+        if lower.ends_with(".bed") {
+            Some("bed".to_string())
+        } else if lower.ends_with(".gff3") || lower.ends_with(".gff") {
+            Some("gff3".to_string())
+        } else if lower.ends_with(".paf") {
+            Some("paf".to_string())
+        } else if lower.ends_with("full_table.tsv") {
+            Some("busco".to_string())
+        } else {
+            None
+        }
+    }
+
+    fn flip_strand(strand: &str) -> String {
+        match strand {
+            "+" => "-".to_string(),
+            "-" => "+".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    fn lift_bed_extras(
+        fields: &mut [String],
+        original_start: usize,
+        lifted: &Lifted,
+    ) -> Result<(), String> {
+        //
+        // thickStart/thickEnd are lifted like the feature, and the
+        // blocks of a BED12 are reversed when the feature is flipped,
+        // they have to be inside the feature to be flipped
+        //
+        if fields.len() >= 8 {
+            if let (Ok(thick_start), Ok(thick_end)) =
+                (fields[6].parse::<usize>(), fields[7].parse::<usize>())
+            {
+                let offset_start = thick_start.saturating_sub(original_start);
+                let offset_end = thick_end.saturating_sub(original_start);
+                let length = lifted.end + 1 - lifted.start;
+                let (new_start, new_end) = if lifted.flipped {
+                    (
+                        length.saturating_sub(offset_end),
+                        length.saturating_sub(offset_start),
+                    )
+                } else {
+                    (offset_start, offset_end)
+                };
+                fields[6] = (lifted.start - 1 + new_start).to_string();
+                fields[7] = (lifted.start - 1 + new_end).to_string();
+            }
+        }
+        if fields.len() >= 12 {
+            let length = lifted.end + 1 - lifted.start;
+            let sizes: Vec<usize> = fields[10]
+                .split(',')
+                .filter_map(|x| x.parse::<usize>().ok())
+                .collect();
+            let starts: Vec<usize> = fields[11]
+                .split(',')
+                .filter_map(|x| x.parse::<usize>().ok())
+                .collect();
+            if let Some((size, start)) = sizes
+                .iter()
+                .zip(&starts)
+                .find(|(size, start)| *start + *size > length)
+            {
+                return Err(format!(
+                    "block {}-{} is outside of the {} bp feature",
+                    start,
+                    start + size,
+                    length
+                ));
+            }
+            if sizes.len() == starts.len() && lifted.flipped {
+                let new_sizes: Vec<String> = sizes.iter().rev().map(|x| x.to_string()).collect();
+                let new_starts: Vec<String> = sizes
+                    .iter()
+                    .zip(&starts)
+                    .rev()
+                    .map(|(size, start)| (length - start - size).to_string())
+                    .collect();
+                fields[10] = format!("{},", new_sizes.join(","));
+                fields[11] = format!("{},", new_starts.join(","));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn lift_line(
+        line: &str,
+        format: &str,
+        columns: &Columns,
+        lift_map: &LiftMap,
+    ) -> Result<String, String> {
+        // Lift a single feature line, or say why it couldn't be
+        let mut fields: Vec<String> = line.split('\t').map(|x| x.to_string()).collect();
+        if fields.len() <= columns.end {
+            return Err("not enough columns".to_string());
+        }
+
+        let start = fields[columns.start]
+            .parse::<usize>()
+            .map_err(|_| format!("'{}' is not a valid start", fields[columns.start]))?;
+        let end = fields[columns.end]
+            .parse::<usize>()
+            .map_err(|_| format!("'{}' is not a valid end", fields[columns.end]))?;
+        // Everything is 1-based inclusive inside
+        let (one_start, one_end) = if columns.zero_based {
+            (start + 1, end)
+        } else {
+            (start, end)
+        };
+        if one_start > one_end {
+            return Err(format!("{}-{} is not a valid interval", start, end));
+        }
+
+        let lifted = lift_map.lift(&fields[columns.name], one_start, one_end)?;
+
+        fields[columns.name] = lifted.scaffold.to_owned();
+        fields[columns.start] = if columns.zero_based {
+            (lifted.start - 1).to_string()
+        } else {
+            lifted.start.to_string()
+        };
+        fields[columns.end] = lifted.end.to_string();
+        if lifted.flipped {
+            if let Some(strand) = columns.strand.filter(|x| *x < fields.len()) {
+                fields[strand] = flip_strand(&fields[strand]);
+            }
+        }
+        if let Some(length) = columns.length.filter(|x| *x < fields.len()) {
+            fields[length] = lift_map.scaffold_lengths[&lifted.scaffold].to_string();
+        }
+        if format == "bed" {
+            lift_bed_extras(&mut fields, start, &lifted)?;
+        }
+        Ok(fields.join("\t"))
+    }
+
+    pub fn lift_features(
+        input: &mut dyn BufRead,
+        format: &str,
+        columns: &Columns,
+        lift_map: &LiftMap,
+        lifted_out: &mut dyn Write,
+        unlifted_out: &mut dyn Write,
+    ) -> Result<(usize, usize), Box<dyn Error>> {
+        //
+        // Stream the features through, returning the number lifted and not.
+        // Comments and headers are kept, apart from the GFF3
+        // sequence-region pragmas which would be wrong, and anything
+        // after a GFF3 ##FASTA is dropped.
+        // Unlifted features go to unlifted_out as REASON<TAB>LINE
+        //
+        let (mut lifted, mut unlifted): (usize, usize) = (0, 0);
+        for line in input.lines() {
+            let line = line?;
+            if format == "gff3" && line.starts_with("##FASTA") {
+                break;
+            }
+            if format == "gff3" && line.starts_with("##sequence-region") {
+                continue;
+            }
+            if line.starts_with('#')
+                || line.trim().is_empty()
+                || (format == "bed" && (line.starts_with("track") || line.starts_with("browser")))
+            {
+                writeln!(lifted_out, "{}", line)?;
+                continue;
+            }
+            // BUSCO Missing genes have no location
+            if format == "busco" && line.split('\t').count() <= columns.end {
+                writeln!(lifted_out, "{}", line)?;
+                continue;
+            }
+
+            match lift_line(&line, format, columns, lift_map) {
+                Ok(new_line) => {
+                    writeln!(lifted_out, "{}", new_line)?;
+                    lifted += 1;
+                }
+                Err(reason) => {
+                    writeln!(unlifted_out, "{}\t{}", reason, line)?;
+                    unlifted += 1;
+                }
+            }
+        }
+        Ok((lifted, unlifted))
+    }
+
+    pub fn liftover(arguments: std::option::Option<&ArgMatches>) {
+        let layout: &String = arguments.unwrap().get_one::<String>("tpf").unwrap();
+        let layout_format: &String = arguments.unwrap().get_one::<String>("format").unwrap();
+        let input: &String = arguments.unwrap().get_one::<String>("input").unwrap();
+        let output: &String = arguments.unwrap().get_one::<String>("output").unwrap();
+        let paf_side: &String = arguments.unwrap().get_one::<String>("paf_side").unwrap();
+        let n_length: &usize = arguments.unwrap().get_one::<usize>("n_length").unwrap();
+
+        let format = match arguments.unwrap().get_one::<String>("features") {
+            Some(format) if format != "auto" => format.to_owned(),
+            _ => match detect_format(input) {
+                Some(format) => format,
+                None => panic!(
+                    "Unable to tell the format of {} from its extension, use -f",
+                    input
+                ),
+            },
+        };
+        let columns = match feature_columns(&format, paf_side) {
+            Ok(columns) => columns,
+            Err(e) => panic!("{}", e),
+        };
+
+        // The layout is turned into scaffolds, and named, exactly as curate does
+        let use_agp = match layout_format.as_str() {
+            "agp" => true,
+            "tpf" => false,
+            _ => is_agp(layout),
+        };
+        let entries: Vec<TpfEntry> = match read_layout(layout, if use_agp { "agp" } else { "tpf" })
+        {
+            Ok(entries) => entries.into_iter().map(|x| x.1).collect(),
+            Err(e) => panic!("Something is wrong with the layout! | {}", e),
+        };
+        let entries = if use_agp || entries.iter().any(|x| matches!(x, TpfEntry::Gap(_))) {
+            entries
+        } else {
+            gapped_tpf(components(&entries), n_length.to_owned())
+        };
+        let entries = match apply_naming(entries, &naming_scheme(arguments.unwrap())) {
+            Ok(entries) => entries,
+            Err(e) => panic!("Something is wrong with the naming! | {}", e),
+        };
+        let lift_map = LiftMap::new(&entries, n_length.to_owned());
+
+        let unlifted_file = Path::new(output)
+            .with_extension("unlifted.tsv")
+            .to_string_lossy()
+            .to_string();
+        println!("Lifting {} ({}) over to: {}", input, format, output);
+
+        let mut reader = open_reader(input).expect("Unable to open input");
+        let mut lifted_out = BufWriter::new(File::create(output).expect("creation failed"));
+        let mut unlifted_out =
+            BufWriter::new(File::create(&unlifted_file).expect("creation failed"));
+
+        match lift_features(
+            &mut reader,
+            &format,
+            &columns,
+            &lift_map,
+            &mut lifted_out,
+            &mut unlifted_out,
+        ) {
+            Ok((lifted, unlifted)) => {
+                println!(
+                    "{}\n{}\n\t{}",
+                    format!("{} FEATURES LIFTED", lifted).green(),
+                    "FOUND HERE:".green(),
+                    output.green()
+                );
+                if unlifted > 0 {
+                    println!(
+                        "{}\n\t{}",
+                        format!("{} FEATURES COULD NOT BE LIFTED, SEE:", unlifted).yellow(),
+                        unlifted_file.yellow()
+                    );
+                }
+            }
+            Err(e) => panic!("Something is wrong with the file! | {}", e),
+        }
+    }
+}
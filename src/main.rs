@@ -1,6 +1,6 @@
 #![allow(non_snake_case)]
 
-use clap::{command, Arg, Command};
+use clap::{builder::PossibleValue, command, Arg, Command};
 use colored::Colorize;
 use std::env;
 use std::io::Error;
@@ -23,8 +23,16 @@ use crate::split_by_count::split_by_count_mod::split_file_by_count;
 mod generics;
 //use crate::generics::validate_fasta;
 
-mod tpf_fasta;
-use crate::tpf_fasta::tpf_fasta_mod::curate_fasta;
+// Built as part of the library, where they are tested
+use fasta_manipulation::extract_mod::extract;
+use fasta_manipulation::faidx_mod::faidx;
+use fasta_manipulation::gencsv_mod::gencsv;
+use fasta_manipulation::liftover_mod::liftover;
+use fasta_manipulation::merge_haps_mod::merge_haplotypes;
+use fasta_manipulation::profile_mod::profile;
+use fasta_manipulation::remap_file_mod::remap_file;
+use fasta_manipulation::subset_mod::subset;
+use fasta_manipulation::tpf_fasta_mod::{curate_fasta, validate_tpf};
 
 mod filter_fasta;
 use crate::filter_fasta::filter_fasta_mod::filter_fasta;
@@ -96,6 +104,12 @@ fn main() -> Result<(), Error> {
                     .value_parser(clap::value_parser!(u16))
                     .help("How many sequences per file")
             )
+            .arg(
+                Arg::new("compress")
+                    .short('z')
+                    .value_parser(clap::builder::PossibleValuesParser::new(["none", "gzip", "bgzip"]))
+                    .help("Compress the output fastas, adding .gz to their names. bgzip also writes a .fai and .gzi for each")
+            )
     )
     .subcommand(
         Command::new("splitbysize")
@@ -131,6 +145,12 @@ fn main() -> Result<(), Error> {
                     .default_value("./")
                     .help("The output directory that files will be placed in")
             )
+            .arg(
+                Arg::new("compress")
+                    .short('z')
+                    .value_parser(clap::builder::PossibleValuesParser::new(["none", "gzip", "bgzip"]))
+                    .help("Compress the output fastas, adding .gz to their names. bgzip also writes a .fai and .gzi for each")
+            )
     )
     .subcommand(
         Command::new("geneset_csvs")
@@ -146,7 +166,7 @@ fn main() -> Result<(), Error> {
                     .short('c')
                     .required(true)
                     .default_value("ALL")
-                    .help("Specify the clade folder to refresh")
+                    .help("Specify the clade folder to refresh, ALL refreshes every clade")
             )
     )
     .subcommand(
@@ -168,7 +188,50 @@ fn main() -> Result<(), Error> {
                 Arg::new("replace-with")
                     .short('r')
                     .default_value("FMMH")
-                    .help("The new header format, appended with a numerical value. Without being set the new header will default to 'FMMH_{numberical}'")
+                    .help("The {prefix} of the new headers. Without being set the new header will default to 'FMMH_{numberical}'")
+            )
+            .arg(
+                Arg::new("template")
+                    .short('t')
+                    .default_value("{prefix}_{idx}")
+                    .help("The template of the new headers, made from {prefix}, {idx} (or zero padded {idx:06}), {name} the old name, {cap:N} group N of the -e regex, {len} and {type}")
+            )
+            .arg(
+                Arg::new("regex")
+                    .short('e')
+                    .help("A regex matched against each old header (with its description), for {cap:N}")
+            )
+            .arg(
+                Arg::new("data_type")
+                    .short('d')
+                    .help("The data type of the input data, for {type}")
+            )
+            .arg(
+                Arg::new("max-length")
+                    .short('l')
+                    .value_parser(clap::value_parser!(usize))
+                    .default_value("255")
+                    .help("The longest a new header can be")
+            )
+            .arg(
+                Arg::new("order")
+                    .short('s')
+                    .value_parser(clap::builder::PossibleValuesParser::new(["input", "length"]))
+                    .default_value("input")
+                    .help("Number the new headers in the order of the fasta (input) or longest first (length)")
+            )
+            .arg(
+                Arg::new("keep-description")
+                    .short('k')
+                    .value_parser(clap::value_parser!(bool))
+                    .default_value("false")
+                    .help("Keep the description after the header, by default only the new name is written")
+            )
+            .arg(
+                Arg::new("compress")
+                    .short('z')
+                    .value_parser(clap::builder::PossibleValuesParser::new(["none", "gzip", "bgzip"]))
+                    .help("Compress the mapped fasta, adding .gz to its name. bgzip also writes a .fai and .gzi")
             )
     )
     .subcommand(
@@ -192,6 +255,75 @@ fn main() -> Result<(), Error> {
                     .required(true)
                     .help("The original mapped header field, a TSV of old-header, new-header")
             )
+            .arg(
+                Arg::new("keep-description")
+                    .short('k')
+                    .value_parser(clap::value_parser!(bool))
+                    .default_value("false")
+                    .help("Keep the description after the header, by default only the new name is written")
+            )
+            .arg(
+                Arg::new("check-checksum")
+                    .short('c')
+                    .value_parser(clap::value_parser!(bool))
+                    .default_value("true")
+                    .help("Check the fasta is the one the map-file was made with, using the md5 in its header")
+            )
+            .arg(
+                Arg::new("unmapped")
+                    .short('u')
+                    .value_parser(clap::builder::PossibleValuesParser::new(["error", "keep", "auto"]))
+                    .default_value("error")
+                    .help("What to do with a header not in the map-file: stop (error), leave it as it is (keep) or give it the next free {auto-prefix}_{number} (auto)")
+            )
+            .arg(
+                Arg::new("auto-prefix")
+                    .short('a')
+                    .default_value("UNMAPPED")
+                    .help("The prefix of names given with -u auto, these are saved to {output-directory}_OH.added-heads.tsv")
+            )
+            .arg(
+                Arg::new("compress")
+                    .short('z')
+                    .value_parser(clap::builder::PossibleValuesParser::new(["none", "gzip", "bgzip"]))
+                    .help("Compress the remapped fasta, adding .gz to its name. bgzip also writes a .fai and .gzi")
+            )
+    )
+    .subcommand(
+        Command::new("remapfile")
+            .about("Subcommand for renaming the sequences of a BED, GFF3, PAF, AGP, TPF or SAM file (or the header of a CRAM) with a mapped-heads.tsv")
+            .arg(
+                Arg::new("input")
+                    .short('i')
+                    .required(true)
+                    .help("The file to rename the sequences of")
+            )
+            .arg(
+                Arg::new("map-file")
+                    .short('m')
+                    .required(true)
+                    .help("The mapped header file, a TSV of old-header, new-header")
+            )
+            .arg(
+                Arg::new("output")
+                    .short('o')
+                    .required(true)
+                    .help("The renamed file, for a CRAM this is its renamed header as SAM")
+            )
+            .arg(
+                Arg::new("format")
+                    .short('F')
+                    .value_parser(clap::builder::PossibleValuesParser::new(["auto", "bed", "gff3", "paf", "agp", "tpf", "sam", "cram"]))
+                    .default_value("auto")
+                    .help("The format of the input, auto uses its extension")
+            )
+            .arg(
+                Arg::new("direction")
+                    .short('d')
+                    .value_parser(clap::builder::PossibleValuesParser::new(["back", "forward"]))
+                    .default_value("back")
+                    .help("back renames new-headers to the old-headers, like remapheaders, forward renames old-headers to the new-headers, like mapheaders")
+            )
     )
     .subcommand(
         Command::new("profile")
@@ -206,12 +338,35 @@ fn main() -> Result<(), Error> {
             Arg::new("output-dir")
                 .short('o')
                 .default_value("FasMan-out")
-                .help("The input fasta file for profiling")
+                .help("The output directory for the summary (txt + json) and per-sequence (tsv) profiles")
+        )
+    )
+    .subcommand(
+        Command::new("validatetpf")
+        .about("Check a tpf (or agp) file against the fasta it re-organises, before curating")
+        .arg(
+            Arg::new("fasta")
+                .short('f')
+                .required(true)
+                .help("The input fasta file, its .fai is used for the lengths when present")
+        )
+        .arg(
+            Arg::new("tpf")
+                .short('t')
+                .required(true)
+                .help("The TPF (or AGP 2.1) file to check")
+        )
+        .arg(
+            Arg::new("format")
+                .short('F')
+                .value_parser(clap::builder::PossibleValuesParser::new(["auto", "tpf", "agp"]))
+                .default_value("auto")
+                .help("Format of the file given to -t, auto detects AGP by extension or by its component type column")
         )
     )
     .subcommand(
         Command::new("curate")
-        .about("Convert an tpf file and original fasta file into a fasta file - useful for curation")
+        .about("Convert an tpf (or agp) file and original fasta file into a fasta file - useful for curation")
         .arg(
             Arg::new("fasta")
                 .short('f')
@@ -222,14 +377,72 @@ fn main() -> Result<(), Error> {
             Arg::new("tpf")
                 .short('t')
                 .required(true)
-                .help("The TPF file used to re-organise the input fasta")
+                .help("The TPF (or AGP 2.1) file used to re-organise the input fasta")
+        )
+        .arg(
+            Arg::new("format")
+                .short('F')
+                .value_parser(clap::builder::PossibleValuesParser::new(["auto", "tpf", "agp"]))
+                .default_value("auto")
+                .help("Format of the file given to -t, auto detects AGP by extension or by its component type column")
         )
         .arg(
             Arg::new("sort")
                 .short('s')
+                .value_parser(clap::builder::PossibleValuesParser::new([
+                    PossibleValue::new("none").alias("false"),
+                    PossibleValue::new("size").alias("true"),
+                    PossibleValue::new("name"),
+                ]))
+                .default_value("none")
+                .help("Size sort (largest first) or natural name sort (SUPER_1, SUPER_2 ... SUPER_10) the output, or leave as order in TPF/AGP")
+        )
+        .arg(
+            Arg::new("chromosomes_first")
+                .short('c')
+                .value_parser(clap::value_parser!(bool))
+                .default_value("false")
+                .help("Keep unlocalised and unplaced scaffolds after all of the chromosomes")
+        )
+        .arg(
+            Arg::new("debug")
+                .short('d')
+                .help("Path for the debug trace, a tsv of where each component went. Default is the output name with a .debug.tsv extension")
+        )
+        .arg(
+            Arg::new("no_debug")
+                .short('D')
                 .value_parser(clap::value_parser!(bool))
                 .default_value("false")
-                .help("Size sort the output or leave as order in AGP")
+                .help("Don't write the debug trace")
+        )
+        .arg(
+            Arg::new("prefix")
+                .short('p')
+                .default_value("SUPER")
+                .help("Prefix of the chromosomes in the output, e.g. SUPER_1")
+        )
+        .arg(
+            Arg::new("tpf_prefix")
+                .short('r')
+                .default_value("RL")
+                .help("Prefix of the chromosomes in the TPF, e.g. RL_1")
+        )
+        .arg(
+            Arg::new("unloc_suffix")
+                .short('u')
+                .default_value("unloc")
+                .help("Name given to unlocalised scaffolds, e.g. SUPER_1_unloc_1")
+        )
+        .arg(
+            Arg::new("unplaced_suffix")
+                .short('U')
+                .help("Suffix added to the names of unplaced scaffolds, by default they keep their name")
+        )
+        .arg(
+            Arg::new("chromosome_map")
+                .short('m')
+                .help("Tab separated chromosome assignments, TPF_NAME CHROMOSOME [LOCATION], e.g. RL_3 X or SCAFFOLD_20 MT Mitochondrion")
         )
         .arg(
             Arg::new("output")
@@ -237,6 +450,17 @@ fn main() -> Result<(), Error> {
                 .default_value("new.fasta")
                 .help("The output name of the new fasta file")
         )
+        .arg(
+            Arg::new("agp")
+                .short('a')
+                .help("The output name of the AGP 2.1 describing the new fasta file, defaults to the output name with an .agp extension")
+        )
+        .arg(
+            Arg::new("compress")
+                .short('z')
+                .value_parser(clap::builder::PossibleValuesParser::new(["none", "gzip", "bgzip"]))
+                .help("Compression of the new fasta, by default .gz outputs are bgzipped (with a .fai and .gzi) and anything else is plain text")
+        )
         .arg(
             Arg::new("n_length")
                 .value_parser(clap::value_parser!(usize))
@@ -244,28 +468,228 @@ fn main() -> Result<(), Error> {
                 .help("Length that the N (gap) string should be.")
         )
     )
+    .subcommand(
+        Command::new("faidx")
+        .about("Index a fasta file (plain or bgzipped), rebuilding a missing or out of date .fai, and extract regions from it like samtools faidx")
+        .arg(
+            Arg::new("fasta-file")
+                .short('f')
+                .required(true)
+                .help("The fasta file to index")
+        )
+        .arg(
+            Arg::new("regions")
+                .num_args(0..)
+                .help("Regions to extract, NAME, NAME:START or NAME:START-END (1-based, inclusive)")
+        )
+        .arg(
+            Arg::new("region_file")
+                .short('r')
+                .help("A file of regions to extract, one per line")
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .help("Write the regions here rather than to stdout")
+        )
+        .arg(
+            Arg::new("line_width")
+                .short('l')
+                .value_parser(clap::value_parser!(usize))
+                .default_value("60")
+                .help("Bases per line of the extracted regions")
+        )
+        .arg(
+            Arg::new("compress")
+                .short('z')
+                .value_parser(clap::builder::PossibleValuesParser::new(["none", "gzip", "bgzip"]))
+                .help("Compression of the output, by default .gz outputs are bgzipped (with a .fai and .gzi) and anything else is plain text")
+        )
+    )
+    .subcommand(
+        Command::new("extract")
+        .about("Extract regions of a fasta file, given on the command line or as a BED file, read through its index so the whole fasta is never loaded")
+        .arg(
+            Arg::new("fasta-file")
+                .short('f')
+                .required(true)
+                .help("The fasta file to extract from, plain or bgzipped")
+        )
+        .arg(
+            Arg::new("regions")
+                .num_args(0..)
+                .help("Regions to extract, NAME, NAME:START or NAME:START-END (1-based, inclusive)")
+        )
+        .arg(
+            Arg::new("bed")
+                .short('b')
+                .help("A BED file of regions to extract, the 4th column is used as the name")
+        )
+        .arg(
+            Arg::new("reverse")
+                .short('i')
+                .value_parser(clap::value_parser!(bool))
+                .default_value("false")
+                .help("Reverse complement the extracted regions")
+        )
+        .arg(
+            Arg::new("strand")
+                .short('s')
+                .value_parser(clap::value_parser!(bool))
+                .default_value("false")
+                .help("Reverse complement BED regions on the - strand (6th column)")
+        )
+        .arg(
+            Arg::new("naming")
+                .short('n')
+                .default_value("{name}")
+                .help("Header of each extracted region, using {name} (BED name or the region), {region}, {scaffold}, {start}, {end} and {strand}")
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .help("Write the regions here rather than to stdout")
+        )
+        .arg(
+            Arg::new("line_width")
+                .short('l')
+                .value_parser(clap::value_parser!(usize))
+                .default_value("60")
+                .help("Bases per line of the extracted regions")
+        )
+        .arg(
+            Arg::new("compress")
+                .short('z')
+                .value_parser(clap::builder::PossibleValuesParser::new(["none", "gzip", "bgzip"]))
+                .help("Compression of the output, by default .gz outputs are bgzipped (with a .fai and .gzi) and anything else is plain text")
+        )
+    )
+    .subcommand(
+        Command::new("liftover")
+        .about("Lift features (BED, GFF3, PAF, BUSCO full_table.tsv) from the original assembly to the one made by curate")
+        .arg(
+            Arg::new("tpf")
+                .short('t')
+                .required(true)
+                .help("The TPF (or AGP 2.1) used by curate, the AGP written by curate works as well")
+        )
+        .arg(
+            Arg::new("format")
+                .short('F')
+                .value_parser(clap::builder::PossibleValuesParser::new(["auto", "tpf", "agp"]))
+                .default_value("auto")
+                .help("Format of the file given to -t, auto detects AGP by extension or by its component type column")
+        )
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .required(true)
+                .help("The features to lift, in original assembly coordinates")
+        )
+        .arg(
+            Arg::new("features")
+                .short('f')
+                .value_parser(clap::builder::PossibleValuesParser::new(["auto", "bed", "gff3", "paf", "busco"]))
+                .default_value("auto")
+                .help("Format of the features, auto uses the extension (.bed, .gff/.gff3, .paf, full_table.tsv)")
+        )
+        .arg(
+            Arg::new("paf_side")
+                .short('s')
+                .value_parser(clap::builder::PossibleValuesParser::new(["target", "query"]))
+                .default_value("target")
+                .help("Which side of a PAF is the original assembly")
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .required(true)
+                .help("The lifted features, unlifted features are written next to it with an .unlifted.tsv extension")
+        )
+        .arg(
+            Arg::new("n_length")
+                .short('n')
+                .value_parser(clap::value_parser!(usize))
+                .default_value("200")
+                .help("Length of the gaps of unknown size, as given to curate")
+        )
+        .arg(
+            Arg::new("prefix")
+                .short('p')
+                .default_value("SUPER")
+                .help("Prefix of the chromosomes, as given to curate")
+        )
+        .arg(
+            Arg::new("tpf_prefix")
+                .short('r')
+                .default_value("RL")
+                .help("Prefix of the chromosomes in the TPF, as given to curate")
+        )
+        .arg(
+            Arg::new("unloc_suffix")
+                .short('u')
+                .default_value("unloc")
+                .help("Name given to unlocalised scaffolds, as given to curate")
+        )
+        .arg(
+            Arg::new("unplaced_suffix")
+                .short('U')
+                .help("Suffix added to the names of unplaced scaffolds, as given to curate")
+        )
+        .arg(
+            Arg::new("chromosome_map")
+                .short('m')
+                .help("Tab separated chromosome assignments, as given to curate")
+        )
+    )
     .subcommand(
         Command::new("subset")
-        .about("Subset a fasta file in a random manner by percentage of file")
+        .about("Subset a fasta file by percentage of records or bases, either from the top of the file or in a seeded random manner")
         .arg(
             Arg::new("fasta-file")
                 .short('f')
                 .required(true)
-                .help("The input fasta file for profiling")
+                .help("The input fasta file for subsetting")
         )
         .arg(
             Arg::new("random")
                 .short('r')
                 .value_parser(clap::value_parser!(bool))
+                .default_value("false")
                 .help("Random subset of input file. Default skims the first X given percent")
         )
         .arg(
             Arg::new("percent")
                 .short('p')
-                .value_parser(clap::value_parser!(u16))
+                .value_parser(clap::value_parser!(u16).range(0..=100))
                 .default_value("50")
                 .help("Percentage of the original file entries that should be retained")
         )
+        .arg(
+            Arg::new("by")
+                .short('b')
+                .value_parser(clap::builder::PossibleValuesParser::new(["records", "bases"]))
+                .default_value("records")
+                .help("Whether percent is of the number of records or of the total bases")
+        )
+        .arg(
+            Arg::new("seed")
+                .short('s')
+                .value_parser(clap::value_parser!(u64))
+                .help("Seed for the random subset, the seed used is always printed so a subset can be regenerated")
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .default_value("subset.fa")
+                .help("The output name of the subset fasta file")
+        )
+        .arg(
+            Arg::new("compress")
+                .short('z')
+                .value_parser(clap::builder::PossibleValuesParser::new(["none", "gzip", "bgzip"]))
+                .help("Compression of the output, by default .gz outputs are bgzipped (with a .fai and .gzi) and anything else is plain text")
+        )
     )
     .subcommand(
         Command::new("filterfasta")
@@ -286,14 +710,44 @@ fn main() -> Result<(), Error> {
                     .short('l')
                     .required(false)
                     .default_value("None")
-                    .help("A string comma-separated list of sequence names to exclude from the final fasta")
+                    .help("A string comma-separated list of sequence names to exclude (or keep) from the final fasta")
             )
             .arg(
                 Arg::new("filter_file")
                     .short('f')
                     .required(false)
                     .default_value("None")
-                    .help("A txt file (such as names.lst) with a sequence header per line to exclude from a final fasta file")
+                    .help("A txt file (such as names.lst) with a sequence header per line to exclude (or keep) from a final fasta file")
+            )
+            .arg(
+                Arg::new("mode")
+                    .short('m')
+                    .value_parser(clap::builder::PossibleValuesParser::new(["exclude", "keep"]))
+                    .default_value("exclude")
+                    .help("Whether sequences matching the names, prefixes or regexes are removed (exclude) or are the only ones kept (keep)")
+            )
+            .arg(
+                Arg::new("prefix")
+                    .short('x')
+                    .help("A comma-separated list of name prefixes to match, e.g. scaffold_,ctg")
+            )
+            .arg(
+                Arg::new("regex")
+                    .short('e')
+                    .action(clap::ArgAction::Append)
+                    .help("A regex to match names against, e.g. '^scaffold_.*_ctg1$', can be given more than once")
+            )
+            .arg(
+                Arg::new("min_length")
+                    .short('n')
+                    .value_parser(clap::value_parser!(usize))
+                    .help("Remove sequences shorter than this many bp, in either mode")
+            )
+            .arg(
+                Arg::new("compress")
+                    .short('z')
+                    .value_parser(clap::builder::PossibleValuesParser::new(["none", "gzip", "bgzip"]))
+                    .help("Compression of the output, by default .gz outputs are bgzipped (with a .fai and .gzi) and anything else is plain text")
             )
     )
     .subcommand(
@@ -303,30 +757,44 @@ fn main() -> Result<(), Error> {
             Arg::new("fasta-1")
                 .short('p')
                 .required(true)
-                .help("The input fasta file for re-organising")
+                .help("The primary input fasta file")
         )
         .arg(
             Arg::new("fasta-2")
                 .short('s')
                 .required(true)
-                .help("The second input fasta file")
+                .num_args(1..)
+                .help("The second input fasta file, further files (e.g. hap2, organelles) can follow it")
         )
         .arg(
             Arg::new("naming")
-                .short('s')
+                .short('n')
                 .default_value("PRI/HAP")
                 .help("A '/' separated list with an item per file, these are the namings of the new scaffolds in the merged output")
         )
+        .arg(
+            Arg::new("position")
+                .short('x')
+                .value_parser(clap::builder::PossibleValuesParser::new(["prefix", "suffix"]))
+                .default_value("prefix")
+                .help("Whether the naming is added as a prefix (PRI_SUPER_1) or suffix (SUPER_1_PRI) to the scaffold names")
+        )
         .arg(
             Arg::new("output")
                 .short('o')
                 .default_value("merged")
-                .help("Output file prefix")
+                .help("Output file prefix, generates {output}.fasta and a {output}.mapped-heads.tsv for use with remapfile")
+        )
+        .arg(
+            Arg::new("compress")
+                .short('z')
+                .value_parser(clap::builder::PossibleValuesParser::new(["none", "gzip", "bgzip"]))
+                .help("Compress the merged fasta, adding .gz to its name. bgzip also writes a .fai and .gzi")
         )
     )
     .get_matches();
 
-    println! {
+    let banner = format! {
         "{}\n{}\n{}\nRUNNING SUBCOMMAND: |\n-- {}\nRUNNING ON: |\n-- {}",
         "WELCOME TO Fasta Manipulator".bold(),
         "This has been made to help prep data for use in the Treeval and curationpretext pipelines".bold(),
@@ -334,6 +802,16 @@ fn main() -> Result<(), Error> {
         match_result.subcommand_name().unwrap(),
         env::consts::OS
     };
+    // Without -o these write the fasta to stdout, so it has to stay clean
+    let fasta_to_stdout = matches!(
+        match_result.subcommand(),
+        Some(("extract" | "faidx", matches)) if matches.get_one::<String>("output").is_none()
+    );
+    if fasta_to_stdout {
+        eprintln!("{}", banner);
+    } else {
+        println!("{}", banner);
+    }
 
     match match_result.subcommand_name() {
         // Should really be pulled out into it's own program
@@ -345,16 +823,23 @@ fn main() -> Result<(), Error> {
         Some("splitbycount") => {
             split_file_by_count(match_result.subcommand_matches("splitbycount"))
         }
-        //Some("subset") => subset(match_result.subcommand_matches("subset"))
-        //Some("profile") => profile(match_result.subcommand_matches("profile"))
+        Some("geneset_csvs") => gencsv(match_result.subcommand_matches("geneset_csvs")),
+        Some("subset") => subset(match_result.subcommand_matches("subset")),
+        Some("profile") => profile(match_result.subcommand_matches("profile")),
         Some("mapheaders") => {
             _ = map_fasta_head(match_result.subcommand_matches("mapheaders"));
         }
         Some("remapheaders") => remapping_head(match_result.subcommand_matches("remapheaders")),
+        Some("remapfile") => remap_file(match_result.subcommand_matches("remapfile")),
         Some("filterfasta") => filter_fasta(match_result.subcommand_matches("filterfasta")),
+        Some("mergehaps") => merge_haplotypes(match_result.subcommand_matches("mergehaps")),
 
         // FASTA + TPF = NEW_FASTA
+        Some("validatetpf") => validate_tpf(match_result.subcommand_matches("validatetpf")),
         Some("curate") => curate_fasta(match_result.subcommand_matches("curate")),
+        Some("liftover") => liftover(match_result.subcommand_matches("liftover")),
+        Some("faidx") => faidx(match_result.subcommand_matches("faidx")),
+        Some("extract") => extract(match_result.subcommand_matches("extract")),
 
         _ => {
             unreachable!()
@@ -1,14 +1,17 @@
 pub mod mapping_headers {
     use clap::ArgMatches;
     use colored::Colorize;
+    use noodles::fasta;
+    use regex::Regex;
+    use std::collections::{HashMap, HashSet};
     use std::error::Error;
     use std::fmt;
     use std::fs::File;
-    use std::io::{BufRead, BufReader, BufWriter, Write};
-    use std::iter::Zip;
+    use std::io::{BufRead, BufWriter, Write};
 
-    use crate::generics::only_keys;
-    use crate::generics::validate_fasta;
+    use crate::generics::{
+        open_fasta, open_md5_reader, output_compression, Compression, FastaOutput, Md5Tee,
+    };
 
     #[allow(dead_code)]
     #[derive(Debug, Clone)]
@@ -21,38 +24,291 @@ pub mod mapping_headers {
         }
     }
 
-    #[allow(clippy::explicit_counter_loop)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum MapOrder {
+        // As they are in the fasta
+        Input,
+        // Longest first, ties stay in input order
+        Length,
+    }
+
+    // A record of the fasta before mapping
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct OldHeader {
+        pub name: String,
+        pub description: Option<String>,
+        pub length: usize,
+    }
+
+    impl OldHeader {
+        pub fn header(&self) -> String {
+            match &self.description {
+                Some(description) => format!("{} {}", self.name, description),
+                None => self.name.to_owned(),
+            }
+        }
+    }
+
+    pub fn read_headers(path: &str) -> Result<Vec<OldHeader>, Box<dyn Error>> {
+        // Headers and lengths in file order, duplicates are kept
+        let mut reader = open_fasta(path)?;
+        let mut headers = Vec::new();
+        for result in reader.records() {
+            let record = result?;
+            headers.push(OldHeader {
+                name: std::str::from_utf8(record.name())?.to_string(),
+                description: match record.description() {
+                    Some(x) => Some(std::str::from_utf8(x)?.to_string()),
+                    None => None,
+                },
+                length: record.sequence().len(),
+            });
+        }
+        Ok(headers)
+    }
+
+    pub fn order_headers(
+        headers: Vec<OldHeader>,
+        order: MapOrder,
+    ) -> Result<Vec<OldHeader>, String> {
+        // The order the new headers are numbered in, the same fasta
+        // always gives the same mapping
+        let mut seen: HashSet<&str> = HashSet::new();
+        for header in &headers {
+            if !seen.insert(&header.name) {
+                return Err(format!("{} is in the fasta more than once", header.name));
+            }
+        }
+        let mut headers = headers;
+        if order == MapOrder::Length {
+            headers.sort_by_key(|x| std::cmp::Reverse(x.length));
+        }
+        Ok(headers)
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Part {
+        Text(String),
+        Prefix,
+        // Zero padded to the width
+        Index(usize),
+        Name,
+        Capture(usize),
+        Length,
+        DataType,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct HeaderTemplate {
+        parts: Vec<Part>,
+        pub prefix: String,
+        // Matched against the whole old header for {cap:N}
+        pub pattern: Option<Regex>,
+        pub data_type: Option<String>,
+    }
+
+    impl HeaderTemplate {
+        pub fn new(template: &str, prefix: &str) -> Result<HeaderTemplate, String> {
+            //
+            // {prefix} the -r string, {idx} or {idx:06} the counter,
+            // {name} the old first token, {cap:N} group N of the regex,
+            // {len} the sequence length and {type} the data type
+            //
+            let mut parts: Vec<Part> = Vec::new();
+            let mut rest = template;
+            while let Some(open) = rest.find('{') {
+                if open > 0 {
+                    parts.push(Part::Text(rest[..open].to_string()));
+                }
+                let close = rest[open..]
+                    .find('}')
+                    .ok_or_else(|| format!("{} has an unclosed {{", template))?
+                    + open;
+                let field = &rest[open + 1..close];
+                let (key, argument) = match field.split_once(':') {
+                    Some((key, argument)) => (key, Some(argument)),
+                    None => (field, None),
+                };
+                let number = |x: Option<&str>| -> Result<usize, String> {
+                    x.unwrap_or("0")
+                        .parse::<usize>()
+                        .map_err(|_| format!("{{{}}} needs a number after the :", field))
+                };
+                parts.push(match (key, argument) {
+                    ("prefix", None) => Part::Prefix,
+                    ("idx", _) => Part::Index(number(argument)?),
+                    ("name", None) => Part::Name,
+                    ("cap", Some(_)) => Part::Capture(number(argument)?),
+                    ("len", None) => Part::Length,
+                    ("type", None) => Part::DataType,
+                    _ => return Err(format!("{{{}}} is not a template field", field)),
+                });
+                rest = &rest[close + 1..];
+            }
+            if !rest.is_empty() {
+                parts.push(Part::Text(rest.to_string()));
+            }
+            if parts.is_empty() {
+                return Err("the template is empty".to_string());
+            }
+            Ok(HeaderTemplate {
+                parts,
+                prefix: prefix.to_string(),
+                pattern: None,
+                data_type: None,
+            })
+        }
+
+        pub fn render(&self, index: usize, header: &OldHeader) -> Result<String, String> {
+            let mut name = String::new();
+            for part in &self.parts {
+                match part {
+                    Part::Text(x) => name.push_str(x),
+                    Part::Prefix => name.push_str(&self.prefix),
+                    Part::Index(width) => name.push_str(&format!("{:0width$}", index)),
+                    Part::Name => name.push_str(&header.name),
+                    Part::Length => name.push_str(&header.length.to_string()),
+                    Part::DataType => match &self.data_type {
+                        Some(x) => name.push_str(x),
+                        None => return Err("{type} needs a data type (-d)".to_string()),
+                    },
+                    Part::Capture(group) => {
+                        let pattern = self
+                            .pattern
+                            .as_ref()
+                            .ok_or_else(|| format!("{{cap:{}}} needs a regex (-e)", group))?;
+                        let old = header.header();
+                        let captures = pattern
+                            .captures(&old)
+                            .ok_or_else(|| format!("{} does not match {}", old, pattern))?;
+                        match captures.get(*group) {
+                            Some(x) => name.push_str(x.as_str()),
+                            None => {
+                                return Err(format!(
+                                    "{} has no group {} in {}",
+                                    pattern, group, old
+                                ))
+                            }
+                        }
+                    }
+                }
+            }
+            Ok(name)
+        }
+    }
+
+    pub fn check_names(names: &[String], max_length: usize) -> Result<(), String> {
+        //
+        // The SAM spec reference name rules, as the strictest of the
+        // tools these go on to, no whitespace and nothing that would
+        // be read as a region or a header
+        //
+        let legal =
+            Regex::new(r"^[0-9A-Za-z!#$%&+./:;?@^_|~-][0-9A-Za-z!#$%&*+./:;=?@^_|~-]*$").unwrap();
+        let mut seen: HashSet<&str> = HashSet::new();
+        for name in names {
+            if !legal.is_match(name) {
+                return Err(format!("'{}' is not a legal sequence name", name));
+            }
+            if name.len() > max_length {
+                return Err(format!("{} is longer than {} characters", name, max_length));
+            }
+            if !seen.insert(name) {
+                return Err(format!("{} would be given to more than one sequence", name));
+            }
+        }
+        Ok(())
+    }
+
     pub fn create_mapping(
-        name_vec: Vec<std::string::String>,
-        new_name: &str,
-    ) -> Zip<std::vec::IntoIter<std::string::String>, std::vec::IntoIter<std::string::String>> {
+        headers: &[OldHeader],
+        template: &HeaderTemplate,
+    ) -> Result<Vec<String>, String> {
         // Generate a new mapping for the Fasta
-        //
-        let mut new_heads: Vec<String> = Vec::new();
-        let mut head_counter: i32 = 0;
-        let name_vec_clone = name_vec.clone();
+        headers
+            .iter()
+            .enumerate()
+            .map(|(index, header)| template.render(index, header))
+            .collect()
+    }
+
+    // Bumped when the layout of the mapping file changes
+    pub const MAPPING_VERSION: u32 = 1;
 
-        for _x in name_vec {
-            new_heads.push(format!("{}_{}", new_name, head_counter));
-            head_counter += 1;
+    // The # lines at the top of a mapping file
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct MappingHeader {
+        pub version: u32,
+        pub fasman: String,
+        // Path and md5 of the fasta(s) the old headers came from
+        pub sources: Vec<(String, String)>,
+        // Path and md5 of the fasta with the new headers
+        pub mapped: Option<(String, String)>,
+    }
+
+    impl MappingHeader {
+        pub fn from_checksums(
+            sources: Vec<(String, String)>,
+            mapped: (String, String),
+        ) -> MappingHeader {
+            // Path and md5 pairs already worked out, e.g. while writing the mapped fasta
+            MappingHeader {
+                version: MAPPING_VERSION,
+                fasman: env!("CARGO_PKG_VERSION").to_string(),
+                sources,
+                mapped: Some(mapped),
+            }
+        }
+    }
+
+    impl fmt::Display for MappingHeader {
+        fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            //
+            // #fasman-mapping  version=1   fasman=0.1.5
+            // #source          input.fa    md5=...
+            // #mapped          mapped.fa   md5=...
+            // #old-header      new-header
+            //
+            writeln!(
+                fmt,
+                "#fasman-mapping\tversion={}\tfasman={}",
+                self.version, self.fasman
+            )?;
+            for (path, md5) in &self.sources {
+                writeln!(fmt, "#source\t{}\tmd5={}", path, md5)?;
+            }
+            if let Some((path, md5)) = &self.mapped {
+                writeln!(fmt, "#mapped\t{}\tmd5={}", path, md5)?;
+            }
+            writeln!(fmt, "#old-header\tnew-header")
         }
+    }
 
-        let mapped_heads: Zip<std::vec::IntoIter<String>, std::vec::IntoIter<String>> =
-            name_vec_clone.into_iter().zip(new_heads);
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Mapping {
+        // None for a mapping file from before there was a header
+        pub header: Option<MappingHeader>,
+        // Old and new header, in the order of the file
+        pub pairs: Vec<(String, String)>,
+    }
 
-        mapped_heads
+    impl Mapping {
+        pub fn is_merge(&self) -> bool {
+            // Made by mergehaps, the old headers can be in more than one source
+            self.header.as_ref().is_some_and(|x| x.sources.len() > 1)
+        }
     }
 
     pub fn save_mapping(
         output: &str,
-        mapped: Zip<
-            std::vec::IntoIter<std::string::String>,
-            std::vec::IntoIter<std::string::String>,
-        >,
+        mapped: impl IntoIterator<Item = (String, String)>,
+        header: &MappingHeader,
     ) {
         // Save the header mapping to file
         let f: File = File::create(output).expect("Unable to create file");
         let mut f: BufWriter<File> = BufWriter::new(f);
+        f.write_all(header.to_string().as_bytes())
+            .expect("Unable to write data");
         for map_pair in mapped {
             let line: String = format!("{}\t{}\n", map_pair.0, map_pair.1);
             f.write_all(&line.into_bytes())
@@ -60,39 +316,265 @@ pub mod mapping_headers {
         }
     }
 
-    #[allow(unused_mut)]
+    fn parse_checksum(line: &str) -> Option<(String, String)> {
+        match line.split('\t').collect::<Vec<&str>>().as_slice() {
+            [_, path, md5] if !path.is_empty() => md5
+                .strip_prefix("md5=")
+                .filter(|x| x.len() == 32 && x.chars().all(|x| x.is_ascii_hexdigit()))
+                .map(|x| (path.to_string(), x.to_string())),
+            _ => None,
+        }
+    }
+
+    pub fn read_mapping(input: &mut dyn BufRead) -> Result<Mapping, String> {
+        //
+        // A mapping without the # header lines is read as before, one with
+        // them must be a version this knows and have the column names.
+        // Every old and new header must only be in the mapping once,
+        // other than old headers of a merge.
+        //
+        let mut header: Option<MappingHeader> = None;
+        let mut columns = false;
+        let mut pairs: Vec<(String, String)> = Vec::new();
+        let mut old_lines: HashMap<String, usize> = HashMap::new();
+        let mut new_lines: HashMap<String, usize> = HashMap::new();
+
+        for (index, line) in input.lines().enumerate() {
+            let number = index + 1;
+            let line = line.map_err(|e| format!("line {}: {}", number, e))?;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(version) = line.strip_prefix("#fasman-mapping\t") {
+                let fields: Vec<&str> = version.split('\t').collect();
+                let version = fields
+                    .first()
+                    .and_then(|x| x.strip_prefix("version="))
+                    .and_then(|x| x.parse::<u32>().ok())
+                    .ok_or_else(|| {
+                        format!("line {}: expected version=NUMBER in: {}", number, line)
+                    })?;
+                if version > MAPPING_VERSION {
+                    return Err(format!(
+                        "line {}: version {} is newer than this FasMan reads ({})",
+                        number, version, MAPPING_VERSION
+                    ));
+                }
+                header = Some(MappingHeader {
+                    version,
+                    fasman: fields
+                        .get(1)
+                        .and_then(|x| x.strip_prefix("fasman="))
+                        .unwrap_or_default()
+                        .to_string(),
+                    sources: Vec::new(),
+                    mapped: None,
+                });
+                continue;
+            }
+            if line.starts_with('#') {
+                let header = match header.as_mut() {
+                    Some(header) if pairs.is_empty() => header,
+                    // Comments are only allowed in the header
+                    _ => return Err(format!("line {}: unexpected # line: {}", number, line)),
+                };
+                if line.starts_with("#source\t") || line.starts_with("#mapped\t") {
+                    let checksum = parse_checksum(line).ok_or_else(|| {
+                        format!(
+                            "line {}: expected #source or #mapped<TAB>PATH<TAB>md5=CHECKSUM in: {}",
+                            number, line
+                        )
+                    })?;
+                    if line.starts_with("#source") {
+                        header.sources.push(checksum);
+                    } else {
+                        header.mapped = Some(checksum);
+                    }
+                } else if line == "#old-header\tnew-header" {
+                    columns = true;
+                } else {
+                    return Err(format!("line {}: unknown header line: {}", number, line));
+                }
+                continue;
+            }
+            if header.is_some() && !columns {
+                return Err(format!(
+                    "line {}: the header has no #old-header<TAB>new-header column names",
+                    number
+                ));
+            }
+
+            let (old, new) = match line.split('\t').collect::<Vec<&str>>().as_slice() {
+                [old, new] if !old.is_empty() && !new.is_empty() => {
+                    (old.to_string(), new.to_string())
+                }
+                _ => {
+                    return Err(format!(
+                        "line {}: expected old-header<TAB>new-header in: {}",
+                        number, line
+                    ))
+                }
+            };
+            // A merge of several fastas can have the same old header in each
+            let merged = header.as_ref().is_some_and(|x| x.sources.len() > 1);
+            if let Some(first) = old_lines.insert(old.clone(), number).filter(|_| !merged) {
+                return Err(format!(
+                    "line {}: old header {} is already on line {}",
+                    number, old, first
+                ));
+            }
+            if let Some(first) = new_lines.insert(new.clone(), number) {
+                return Err(format!(
+                    "line {}: new header {} is already on line {}",
+                    number, new, first
+                ));
+            }
+            pairs.push((old, new));
+        }
+        Ok(Mapping { header, pairs })
+    }
+
+    // What to do with a header that isn't in the mapping
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Unmapped {
+        Error,
+        // Left as it is
+        Keep,
+        // Given the next free {prefix}_{number}
+        Auto,
+    }
+
+    impl std::str::FromStr for Unmapped {
+        type Err = String;
+
+        fn from_str(policy: &str) -> Result<Self, Self::Err> {
+            match policy {
+                "error" => Ok(Unmapped::Error),
+                "keep" => Ok(Unmapped::Keep),
+                "auto" => Ok(Unmapped::Auto),
+                _ => Err(format!("{} is not error, keep or auto", policy)),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct RewriteSummary {
+        pub renamed: usize,
+        pub kept: Vec<String>,
+        // Old and new header of those given a name by Unmapped::Auto
+        pub assigned: Vec<(String, String)>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct HeaderRewriter {
+        mapping: HashMap<String, String>,
+        // Every name in use as a new header, auto names must avoid them
+        taken: HashSet<String>,
+        pub keep_description: bool,
+        pub unmapped: Unmapped,
+        pub auto_prefix: String,
+        next_auto: usize,
+    }
+
+    impl HeaderRewriter {
+        pub fn new<K: Into<String>, V: Into<String>>(
+            pairs: impl IntoIterator<Item = (K, V)>,
+        ) -> Result<HeaderRewriter, String> {
+            let mut mapping: HashMap<String, String> = HashMap::new();
+            let mut taken: HashSet<String> = HashSet::new();
+            for (old, new) in pairs {
+                let (old, new): (String, String) = (old.into(), new.into());
+                if !taken.insert(new.clone()) {
+                    return Err(format!("{} is mapped to more than once", new));
+                }
+                if mapping.insert(old.clone(), new).is_some() {
+                    return Err(format!("{} is in the mapping more than once", old));
+                }
+            }
+            Ok(HeaderRewriter {
+                next_auto: mapping.len(),
+                mapping,
+                taken,
+                keep_description: false,
+                unmapped: Unmapped::Error,
+                auto_prefix: "FMMH".to_string(),
+            })
+        }
+
+        pub fn rename(
+            &mut self,
+            name: &str,
+            summary: &mut RewriteSummary,
+        ) -> Result<String, String> {
+            if let Some(new) = self.mapping.get(name) {
+                summary.renamed += 1;
+                return Ok(new.to_owned());
+            }
+            match self.unmapped {
+                Unmapped::Error => Err(format!("{} is not in the mapping", name)),
+                Unmapped::Keep => {
+                    summary.kept.push(name.to_string());
+                    Ok(name.to_string())
+                }
+                Unmapped::Auto => {
+                    let mut new = format!("{}_{}", self.auto_prefix, self.next_auto);
+                    while self.taken.contains(&new) {
+                        self.next_auto += 1;
+                        new = format!("{}_{}", self.auto_prefix, self.next_auto);
+                    }
+                    self.next_auto += 1;
+                    self.taken.insert(new.clone());
+                    // A second record of the same name gets the same new name
+                    self.mapping.insert(name.to_string(), new.clone());
+                    summary.assigned.push((name.to_string(), new.clone()));
+                    Ok(new)
+                }
+            }
+        }
+
+        pub fn rewrite(
+            &mut self,
+            input: &mut dyn BufRead,
+            out: &mut dyn Write,
+        ) -> Result<RewriteSummary, Box<dyn Error>> {
+            // One record at a time, each name is a single lookup
+            let mut summary = RewriteSummary::default();
+            let mut reader = fasta::Reader::new(input);
+            let mut writer = fasta::Writer::new(out);
+            for result in reader.records() {
+                let record = result?;
+                let name = std::str::from_utf8(record.name())?;
+                let new_name = self.rename(name, &mut summary)?;
+                let description = match self.keep_description {
+                    true => record.description().map(|x| x.to_vec()),
+                    false => None,
+                };
+                let definition = fasta::record::Definition::new(new_name, description);
+                writer.write_record(&fasta::Record::new(definition, record.sequence().clone()))?;
+            }
+            Ok(summary)
+        }
+    }
+
     pub fn create_mapped_fasta(
         input: &str,
         output: &str,
-        mapped: Zip<
-            std::vec::IntoIter<std::string::String>,
-            std::vec::IntoIter<std::string::String>,
-        >,
-    ) {
-        // Swap out the old with the new
-        // skip all else.
-        // This could be re-written now that I know more about noodles
-        let file_reader: File = File::open(input).expect("CAN'T OPEN FILE");
-        let buff_reader: BufReader<File> = BufReader::new(file_reader);
-        let mut new_fasta: File = File::create(output).unwrap();
-
-        for line in buff_reader.lines() {
-            let l: &str = &line.as_ref().unwrap()[..];
-            if l.starts_with('>') {
-                let mut to_replace = l.replace('>', "");
-                let mut mapped_heads: Zip<std::vec::IntoIter<String>, std::vec::IntoIter<String>> =
-                    mapped.clone();
-                let mut map: Option<(String, String)> =
-                    mapped_heads.find(|x: &(String, String)| x.0 == to_replace);
-                let mut new_head: String = map.expect("").1;
-                let fmt_head: String = format!(">{}\n", new_head);
-                let _ = new_fasta.write_all(&fmt_head.into_bytes());
-            } else {
-                let mut seq = line.expect("");
-                let fmt_seq = format!("{}\n", seq);
-                let _ = new_fasta.write_all(&fmt_seq.into_bytes());
-            }
-        }
+        rewriter: &mut HeaderRewriter,
+        compression: Compression,
+    ) -> Result<(RewriteSummary, MappingHeader), Box<dyn Error>> {
+        // Swap out the old with the new, the checksums of both
+        // fastas are taken on the way through for the mapping file
+        let mut buff_reader = open_md5_reader(input)?;
+        let mut new_fasta = Md5Tee::new(FastaOutput::create(output, compression)?);
+        let summary = rewriter.rewrite(&mut buff_reader, &mut new_fasta)?;
+        let header = MappingHeader::from_checksums(
+            vec![(input.to_string(), buff_reader.get_ref().md5())],
+            (output.to_string(), new_fasta.md5()),
+        );
+        new_fasta.into_inner().finish()?;
+        Ok((summary, header))
     }
 
     pub fn map_fasta_head(
@@ -110,28 +592,83 @@ pub mod mapping_headers {
             .get_one::<String>("output-directory")
             .unwrap();
 
-        println!("Mapping headers for file: {}", file);
-        println!("Replace headers with string: {:?}", &replacer);
+        let keep_description: &bool = arguments
+            .unwrap()
+            .get_one::<bool>("keep-description")
+            .unwrap();
+        let order = match arguments
+            .unwrap()
+            .get_one::<String>("order")
+            .unwrap()
+            .as_str()
+        {
+            "length" => MapOrder::Length,
+            _ => MapOrder::Input,
+        };
 
-        match validate_fasta(file) {
-            Ok(names) => {
-                // Vec of scaffold names from validate_fasta
-                // return only the headers, not the lengths
-                let new_names = Vec::from_iter(only_keys(names));
+        let template_string: &String = arguments.unwrap().get_one::<String>("template").unwrap();
+        let max_length: &usize = arguments.unwrap().get_one::<usize>("max-length").unwrap();
+        let mut template = match HeaderTemplate::new(template_string, replacer) {
+            Ok(template) => template,
+            Err(e) => {
+                eprintln!("{}", format!("CAN'T USE TEMPLATE: {}", e).red());
+                std::process::exit(1);
+            }
+        };
+        if let Some(pattern) = arguments.unwrap().get_one::<String>("regex") {
+            match Regex::new(pattern) {
+                Ok(regex) => template.pattern = Some(regex),
+                Err(e) => panic!("Something is wrong with the regex! | {}", e),
+            }
+        }
+        template.data_type = arguments.unwrap().get_one::<String>("data_type").cloned();
 
-                // Generate a Zip of the a=old and new names
-                let new_map: Zip<std::vec::IntoIter<String>, std::vec::IntoIter<String>> =
-                    create_mapping(new_names, replacer);
+        println!("Mapping headers for file: {}", file);
+        println!("Replace headers with template: {:?}", &template_string);
 
-                // Save the mapping to file
-                let map_to_save: Zip<std::vec::IntoIter<String>, std::vec::IntoIter<String>> =
-                    new_map.clone();
-                let output_file = format!("{}mapped-heads.tsv", output);
-                save_mapping(&output_file, map_to_save);
+        match read_headers(file) {
+            Ok(headers) => {
+                // Scaffolds in the order they are numbered, then their new names
+                let names = order_headers(headers, order).and_then(|headers| {
+                    let new_names = create_mapping(&headers, &template)?;
+                    check_names(&new_names, *max_length)?;
+                    let old_names: Vec<String> = headers.into_iter().map(|x| x.name).collect();
+                    Ok((old_names, new_names))
+                });
+                let (old_names, new_names) = match names {
+                    Ok(names) => names,
+                    Err(e) => {
+                        eprintln!("{}", format!("CAN'T MAP HEADERS: {}", e).red());
+                        std::process::exit(1);
+                    }
+                };
+
+                // The old and new names, the rewriter keeps its own lookup of them
+                let new_map: Vec<(String, String)> = old_names.into_iter().zip(new_names).collect();
 
                 // Generate a new fasta with the mapped headers
-                let new_fasta: String = format!("{output}mapped.fasta");
-                create_mapped_fasta(file, &new_fasta, new_map);
+                let compression =
+                    output_compression(output, arguments.unwrap().get_one::<String>("compress"));
+                let new_fasta: String = format!("{output}mapped.fasta{}", compression.suffix());
+                let mut rewriter =
+                    match HeaderRewriter::new(new_map.iter().map(|(old, new)| (old, new))) {
+                        Ok(rewriter) => rewriter,
+                        Err(e) => panic!("Something is wrong with the mapping! | {}", e),
+                    };
+                rewriter.keep_description = *keep_description;
+
+                // Save the mapping to file, with the checksums of both fastas
+                // so remapheaders can tell it has the right one
+                let header = match create_mapped_fasta(file, &new_fasta, &mut rewriter, compression)
+                {
+                    Ok((_, header)) => header,
+                    Err(e) => {
+                        eprintln!("{}", format!("CAN'T MAP HEADERS: {}", e).red());
+                        std::process::exit(1);
+                    }
+                };
+                let output_file = format!("{}mapped-heads.tsv", output);
+                save_mapping(&output_file, new_map, &header);
 
                 println!(
                     "{}\n{}\n\t{}\n\t{}",
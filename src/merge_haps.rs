@@ -0,0 +1,125 @@
+pub mod merge_haps_mod {
+    use clap::ArgMatches;
+    use colored::Colorize;
+    use noodles::fasta;
+    use noodles::fasta::record::Definition;
+    use std::collections::HashSet;
+    use std::error::Error;
+    use std::fs;
+
+    use crate::generics::{open_md5_reader, output_compression, Compression, FastaOutput, Md5Tee};
+    use crate::map_headers::mapping_headers::{save_mapping, MappingHeader};
+
+    pub fn new_scaffold_name(name: &str, label: &str, suffix: bool) -> String {
+        // PRI + SUPER_1 = PRI_SUPER_1 (or SUPER_1_PRI)
+        if suffix {
+            format!("{}_{}", name, label)
+        } else {
+            format!("{}_{}", label, name)
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn merge_fastas(
+        inputs: &[(&str, &str)],
+        suffix: bool,
+        output: &str,
+        compression: Compression,
+    ) -> Result<(Vec<(String, String)>, MappingHeader), Box<dyn Error>> {
+        //
+        // Stream each (fasta, label) pair into the output, renaming
+        // the records as we go. Returns the old:new name pairs in the
+        // order written, and the checksums of the fastas taken on the way.
+        // A clash in the new names is an error rather than a silently
+        // duplicated header.
+        //
+        let mut file = Md5Tee::new(FastaOutput::create(output, compression)?);
+        let mut writer = fasta::Writer::new(&mut file);
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut mapping: Vec<(String, String)> = Vec::new();
+        let mut sources: Vec<(String, String)> = Vec::new();
+
+        for (path, label) in inputs {
+            let mut reader = fasta::Reader::new(open_md5_reader(path)?);
+
+            for result in reader.records() {
+                let record = result?;
+                let old_name = std::str::from_utf8(record.name())?.to_string();
+                let new_name = new_scaffold_name(&old_name, label, suffix);
+
+                if !seen.insert(new_name.clone()) {
+                    return Err(format!(
+                        "Name collision: {} (from {} in {}) is already in the merged fasta",
+                        new_name, old_name, path
+                    )
+                    .into());
+                }
+
+                let definition = Definition::new(
+                    new_name.as_bytes(),
+                    record.description().map(|x| x.to_vec()),
+                );
+                writer.write_record(&fasta::Record::new(definition, record.sequence().clone()))?;
+                mapping.push((old_name, new_name));
+            }
+            sources.push((path.to_string(), reader.get_ref().get_ref().md5()));
+        }
+        let header = MappingHeader::from_checksums(sources, (output.to_string(), file.md5()));
+        file.into_inner().finish()?;
+        Ok((mapping, header))
+    }
+
+    pub fn merge_haplotypes(arguments: std::option::Option<&ArgMatches>) {
+        let primary: &String = arguments.unwrap().get_one::<String>("fasta-1").unwrap();
+        let others: Vec<&String> = arguments
+            .unwrap()
+            .get_many::<String>("fasta-2")
+            .unwrap()
+            .collect();
+        let naming: &String = arguments.unwrap().get_one::<String>("naming").unwrap();
+        let suffix: bool = arguments.unwrap().get_one::<String>("position").unwrap() == "suffix";
+        let output: &String = arguments.unwrap().get_one::<String>("output").unwrap();
+
+        let mut files: Vec<&str> = vec![primary];
+        files.extend(others.iter().map(|x| x.as_str()));
+        let labels: Vec<&str> = naming.split('/').collect();
+
+        if files.len() != labels.len() {
+            panic!(
+                "{} fasta files were given but naming ({}) has {} items, there should be one per file",
+                files.len(),
+                naming,
+                labels.len()
+            )
+        }
+
+        let inputs: Vec<(&str, &str)> = files.into_iter().zip(labels).collect();
+        for (file, label) in &inputs {
+            println!("Merging: {} as {}", file, label);
+        }
+
+        let compression =
+            output_compression(output, arguments.unwrap().get_one::<String>("compress"));
+        let new_fasta = format!("{}.fasta{}", output, compression.suffix());
+        let map_file = format!("{}.mapped-heads.tsv", output);
+
+        match merge_fastas(&inputs, suffix, &new_fasta, compression) {
+            Ok((mapping, header)) => {
+                // Same layout as mapheaders so remapfile can take files back to the inputs
+                save_mapping(&map_file, mapping, &header);
+
+                println!(
+                    "{}\n{}\n\t{}\n\t{}",
+                    "FASTA FILES HAVE BEEN MERGED".green(),
+                    "FOUND HERE:".green(),
+                    &new_fasta.green(),
+                    &map_file.green()
+                );
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&new_fasta);
+                panic!("Unable to merge fasta files! | {}", e)
+            }
+        }
+    }
+}
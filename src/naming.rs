@@ -0,0 +1,291 @@
+pub mod naming_mod {
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::fmt;
+    use std::fs::read_to_string;
+    use std::io::Write;
+
+    // What a scaffold of the curated assembly is, worked out from its name
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ScaffoldRole {
+        Chromosome {
+            chromosome: String,
+            chromosome_type: ChromosomeType,
+            location: Option<String>,
+        },
+        Unlocalised {
+            chromosome: String,
+            index: String,
+        },
+        Unplaced,
+    }
+
+    impl ScaffoldRole {
+        pub fn rank(&self) -> usize {
+            // Chromosomes, then unlocalised, then unplaced
+            match self {
+                ScaffoldRole::Chromosome { .. } => 0,
+                ScaffoldRole::Unlocalised { .. } => 1,
+                ScaffoldRole::Unplaced => 2,
+            }
+        }
+    }
+
+    // The CHROMOSOME_TYPE column of an ENA chromosome list
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub enum ChromosomeType {
+        #[default]
+        Chromosome,
+        CircularChromosome,
+        LinkageGroup,
+    }
+
+    impl std::str::FromStr for ChromosomeType {
+        type Err = String;
+
+        fn from_str(chromosome_type: &str) -> Result<Self, Self::Err> {
+            match chromosome_type {
+                "Chromosome" => Ok(ChromosomeType::Chromosome),
+                "Circular-Chromosome" => Ok(ChromosomeType::CircularChromosome),
+                "Linkage-Group" => Ok(ChromosomeType::LinkageGroup),
+                _ => Err(format!(
+                    "{} is not Chromosome, Circular-Chromosome or Linkage-Group",
+                    chromosome_type
+                )),
+            }
+        }
+    }
+
+    impl fmt::Display for ChromosomeType {
+        fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let name = match self {
+                ChromosomeType::Chromosome => "Chromosome",
+                ChromosomeType::CircularChromosome => "Circular-Chromosome",
+                ChromosomeType::LinkageGroup => "Linkage-Group",
+            };
+            write!(fmt, "{}", name)
+        }
+    }
+
+    // A row of the chromosome assignment table
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Assignment {
+        pub chromosome: String,
+        pub chromosome_type: ChromosomeType,
+        // Mitochondrion, Chloroplast etc. None for the nuclear genome
+        pub location: Option<String>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct NamingScheme {
+        // Prefix of the chromosomes in the output, SUPER_1
+        pub prefix: String,
+        // Prefix given to the chromosomes in the TPF, RL_1
+        pub tpf_prefix: String,
+        // SUPER_1_unloc_1
+        pub unloc_suffix: String,
+        // Added to the end of unplaced scaffolds, None keeps their name
+        pub unplaced_suffix: Option<String>,
+        // TPF scaffold name -> chromosome, e.g. RL_3 -> X or SCAFFOLD_20 -> MT
+        pub assignments: HashMap<String, Assignment>,
+    }
+
+    impl Default for NamingScheme {
+        fn default() -> NamingScheme {
+            // The Pretext/agp_to_tpf naming, RL_1 -> SUPER_1
+            NamingScheme {
+                prefix: "SUPER".to_string(),
+                tpf_prefix: "RL".to_string(),
+                unloc_suffix: "unloc".to_string(),
+                unplaced_suffix: None,
+                assignments: HashMap::new(),
+            }
+        }
+    }
+
+    pub fn read_assignments(path: &str) -> Result<HashMap<String, Assignment>, Box<dyn Error>> {
+        //
+        // Tab separated, one scaffold per line, # for comments
+        // RL_3         X
+        // SCAFFOLD_20  MT  Circular-Chromosome  Mitochondrion
+        //
+        let mut assignments: HashMap<String, Assignment> = HashMap::new();
+        for (index, line) in read_to_string(path)?.lines().enumerate() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let columns: Vec<&str> = line.split('\t').map(|x| x.trim()).collect();
+            if columns.len() < 2 || columns[0].is_empty() || columns[1].is_empty() {
+                return Err(format!(
+                    "{} line {}: expected SCAFFOLD<TAB>CHROMOSOME[<TAB>TYPE[<TAB>LOCATION]] in: {}",
+                    path,
+                    index + 1,
+                    line
+                )
+                .into());
+            }
+            let chromosome_type = match columns.get(2).filter(|x| !x.is_empty()) {
+                Some(chromosome_type) => chromosome_type
+                    .parse()
+                    .map_err(|e| format!("{} line {}: {}", path, index + 1, e))?,
+                None => ChromosomeType::default(),
+            };
+            let assignment = Assignment {
+                chromosome: columns[1].to_owned(),
+                chromosome_type,
+                location: columns
+                    .get(3)
+                    .filter(|x| !x.is_empty())
+                    .map(|x| x.to_string()),
+            };
+            if assignments
+                .insert(columns[0].to_owned(), assignment)
+                .is_some()
+            {
+                return Err(format!(
+                    "{} line {}: {} is assigned more than once",
+                    path,
+                    index + 1,
+                    columns[0]
+                )
+                .into());
+            }
+        }
+        Ok(assignments)
+    }
+
+    impl NamingScheme {
+        fn chromosome_prefix<'a>(&self, name: &'a str) -> Option<&'a str> {
+            // Either the TPF (RL_) or output (SUPER_) prefix, so
+            // that the scheme works for a TPF or an already named AGP
+            name.strip_prefix(&format!("{}_", self.tpf_prefix))
+                .or_else(|| name.strip_prefix(&format!("{}_", self.prefix)))
+        }
+
+        fn assignment_of(&self, chromosome: &str) -> Option<&Assignment> {
+            self.assignments
+                .values()
+                .find(|x| x.chromosome == chromosome)
+        }
+
+        fn assigned(&self, name: &str) -> String {
+            // The chromosome for RL_3, either from the table or the 3
+            self.assignments
+                .get(name)
+                .map(|x| x.chromosome.to_owned())
+                .unwrap_or_else(|| self.chromosome_prefix(name).unwrap_or(name).to_string())
+        }
+
+        pub fn role(&self, name: &str) -> ScaffoldRole {
+            if let Some(assignment) = self.assignments.get(name) {
+                return ScaffoldRole::Chromosome {
+                    chromosome: assignment.chromosome.to_owned(),
+                    chromosome_type: assignment.chromosome_type,
+                    location: assignment.location.to_owned(),
+                };
+            }
+
+            if let Some(label) = self.chromosome_prefix(name) {
+                // RL_1_unloc_2, or SUPER_1_{unloc_suffix}_2 once renamed
+                for token in ["unloc", self.unloc_suffix.as_str()] {
+                    let split = format!("_{}_", token);
+                    if let Some(position) = name.rfind(&split) {
+                        return ScaffoldRole::Unlocalised {
+                            chromosome: self.assigned(&name[..position]),
+                            index: name[position + split.len()..].to_string(),
+                        };
+                    }
+                }
+                let assignment = self.assignment_of(label);
+                return ScaffoldRole::Chromosome {
+                    chromosome: label.to_string(),
+                    chromosome_type: assignment.map(|x| x.chromosome_type).unwrap_or_default(),
+                    location: assignment.and_then(|x| x.location.to_owned()),
+                };
+            }
+            ScaffoldRole::Unplaced
+        }
+
+        pub fn rename(&self, name: &str) -> String {
+            match self.role(name) {
+                ScaffoldRole::Chromosome { chromosome, .. } => {
+                    format!("{}_{}", self.prefix, chromosome)
+                }
+                ScaffoldRole::Unlocalised { chromosome, index } => format!(
+                    "{}_{}_{}_{}",
+                    self.prefix, chromosome, self.unloc_suffix, index
+                ),
+                ScaffoldRole::Unplaced => match &self.unplaced_suffix {
+                    Some(suffix) if !name.ends_with(&format!("_{}", suffix)) => {
+                        format!("{}_{}", name, suffix)
+                    }
+                    _ => name.to_owned(),
+                },
+            }
+        }
+
+        pub fn rename_all(
+            &self,
+            names: &[String],
+        ) -> Result<HashMap<String, String>, Box<dyn Error>> {
+            // old -> new for every scaffold, two scaffolds can't end up with one name
+            let mut renamed: HashMap<String, String> = HashMap::new();
+            let mut taken: HashMap<String, String> = HashMap::new();
+            for name in names {
+                let new_name = self.rename(name);
+                if let Some(other) = taken.get(&new_name) {
+                    if other != name {
+                        return Err(format!(
+                            "{} and {} would both be named {}, check the chromosome assignments",
+                            other, name, new_name
+                        )
+                        .into());
+                    }
+                }
+                taken.insert(new_name.to_owned(), name.to_owned());
+                renamed.insert(name.to_owned(), new_name);
+            }
+            Ok(renamed)
+        }
+
+        pub fn write_chromosome_lists(
+            &self,
+            names: &[String],
+            chromosome_out: &mut dyn Write,
+            unlocalised_out: &mut dyn Write,
+        ) -> std::io::Result<()> {
+            //
+            // ENA chromosome list: OBJECT_NAME CHROMOSOME_NAME CHROMOSOME_TYPE [LOCATION]
+            // ENA unlocalised list: OBJECT_NAME CHROMOSOME_NAME
+            // Unplaced scaffolds are in neither
+            //
+            for name in names {
+                match self.role(name) {
+                    ScaffoldRole::Chromosome {
+                        chromosome,
+                        chromosome_type,
+                        location: Some(location),
+                    } => writeln!(
+                        chromosome_out,
+                        "{}\t{}\t{}\t{}",
+                        name, chromosome, chromosome_type, location
+                    )?,
+                    ScaffoldRole::Chromosome {
+                        chromosome,
+                        chromosome_type,
+                        ..
+                    } => writeln!(
+                        chromosome_out,
+                        "{}\t{}\t{}",
+                        name, chromosome, chromosome_type
+                    )?,
+                    ScaffoldRole::Unlocalised { chromosome, .. } => {
+                        writeln!(unlocalised_out, "{}\t{}", name, chromosome)?
+                    }
+                    ScaffoldRole::Unplaced => {}
+                }
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,306 @@
+pub mod profile_mod {
+    use clap::ArgMatches;
+    use colored::Colorize;
+    use std::error::Error;
+    use std::fs::{self, File};
+    use std::io::{BufWriter, Write};
+    use std::path::Path;
+
+    use crate::generics::map_records;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct SequenceProfile {
+        pub name: String,
+        pub length: usize,
+        pub gc_count: usize,
+        pub at_count: usize,
+        pub n_count: usize,
+        pub other_count: usize,
+        pub gap_count: usize,
+    }
+
+    impl SequenceProfile {
+        pub fn from_sequence(name: &str, sequence: &[u8]) -> SequenceProfile {
+            // Count the bases of a single sequence, gaps are counted
+            // as each run of N's rather than each N
+            let mut profile = SequenceProfile {
+                name: name.to_string(),
+                length: sequence.len(),
+                gc_count: 0,
+                at_count: 0,
+                n_count: 0,
+                other_count: 0,
+                gap_count: 0,
+            };
+
+            let mut in_gap = false;
+            for base in sequence {
+                match base.to_ascii_uppercase() {
+                    b'G' | b'C' | b'S' => profile.gc_count += 1,
+                    b'A' | b'T' | b'W' | b'U' => profile.at_count += 1,
+                    b'N' => profile.n_count += 1,
+                    _ => profile.other_count += 1,
+                }
+
+                let is_n = base.eq_ignore_ascii_case(&b'N');
+                if is_n && !in_gap {
+                    profile.gap_count += 1;
+                }
+                in_gap = is_n;
+            }
+            profile
+        }
+
+        pub fn gc_percent(&self) -> f64 {
+            // GC is given as a percentage of the called (non N) bases
+            let called = self.gc_count + self.at_count;
+            if called == 0 {
+                0.0
+            } else {
+                self.gc_count as f64 / called as f64 * 100.0
+            }
+        }
+    }
+
+    pub fn profile_fasta(path: &str) -> Result<Vec<SequenceProfile>, Box<dyn Error>> {
+        // The base composition of each record, in file order
+        map_records(path, SequenceProfile::from_sequence)
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct AssemblyProfile {
+        pub sequence_count: usize,
+        pub total_length: usize,
+        pub n50: usize,
+        pub n90: usize,
+        pub l50: usize,
+        pub l90: usize,
+        pub gc_percent: f64,
+        pub n_count: usize,
+        pub n_percent: f64,
+        pub gap_count: usize,
+        pub longest: (String, usize),
+        pub shortest: (String, usize),
+    }
+
+    impl std::fmt::Display for AssemblyProfile {
+        // Human readable summary, also used for the .txt output
+        fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+            writeln!(fmt, "Sequence count:\t\t{}", self.sequence_count)?;
+            writeln!(fmt, "Total length:\t\t{}", self.total_length)?;
+            writeln!(fmt, "N50:\t\t\t{}", self.n50)?;
+            writeln!(fmt, "L50:\t\t\t{}", self.l50)?;
+            writeln!(fmt, "N90:\t\t\t{}", self.n90)?;
+            writeln!(fmt, "L90:\t\t\t{}", self.l90)?;
+            writeln!(fmt, "GC (%):\t\t\t{:.2}", self.gc_percent)?;
+            writeln!(fmt, "N count:\t\t{}", self.n_count)?;
+            writeln!(fmt, "N (%):\t\t\t{:.2}", self.n_percent)?;
+            writeln!(fmt, "Gap count:\t\t{}", self.gap_count)?;
+            writeln!(
+                fmt,
+                "Longest sequence:\t{} ({})",
+                self.longest.0, self.longest.1
+            )?;
+            write!(
+                fmt,
+                "Shortest sequence:\t{} ({})",
+                self.shortest.0, self.shortest.1
+            )
+        }
+    }
+
+    pub fn nx_lx(lengths: &[usize], fraction: f64) -> (usize, usize) {
+        // Return the Nx and Lx of a list of lengths
+        // e.g. a fraction of 0.5 gives (N50, L50)
+        let mut sorted = lengths.to_vec();
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+
+        let total: usize = sorted.iter().sum();
+        let target = total as f64 * fraction;
+        let mut running: usize = 0;
+        for (index, length) in sorted.iter().enumerate() {
+            running += length;
+            if running as f64 >= target {
+                return (*length, index + 1);
+            }
+        }
+        (0, 0)
+    }
+
+    pub fn summarise(profiles: &[SequenceProfile]) -> AssemblyProfile {
+        // Collapse the per-sequence profiles into one for the whole file
+        let lengths: Vec<usize> = profiles.iter().map(|x| x.length).collect();
+        let (n50, l50) = nx_lx(&lengths, 0.5);
+        let (n90, l90) = nx_lx(&lengths, 0.9);
+
+        let total_length: usize = lengths.iter().sum();
+        let gc_count: usize = profiles.iter().map(|x| x.gc_count).sum();
+        let at_count: usize = profiles.iter().map(|x| x.at_count).sum();
+        let n_count: usize = profiles.iter().map(|x| x.n_count).sum();
+
+        // Ties are given to the first sequence in the file
+        let longest = profiles
+            .iter()
+            .rev()
+            .max_by_key(|x| x.length)
+            .map(|x| (x.name.to_owned(), x.length))
+            .unwrap_or_default();
+        let shortest = profiles
+            .iter()
+            .min_by_key(|x| x.length)
+            .map(|x| (x.name.to_owned(), x.length))
+            .unwrap_or_default();
+
+        AssemblyProfile {
+            sequence_count: profiles.len(),
+            total_length,
+            n50,
+            n90,
+            l50,
+            l90,
+            gc_percent: percent(gc_count, gc_count + at_count),
+            n_count,
+            n_percent: percent(n_count, total_length),
+            gap_count: profiles.iter().map(|x| x.gap_count).sum(),
+            longest,
+            shortest,
+        }
+    }
+
+    fn percent(part: usize, whole: usize) -> f64 {
+        if whole == 0 {
+            0.0
+        } else {
+            part as f64 / whole as f64 * 100.0
+        }
+    }
+
+    fn save_sequence_tsv(output: &str, profiles: &[SequenceProfile]) -> std::io::Result<()> {
+        // One line per sequence, in the order of the input fasta
+        let mut file = BufWriter::new(File::create(output)?);
+        writeln!(
+            file,
+            "name\tlength\tgc_percent\tgc_count\tat_count\tn_count\tother_count\tgap_count"
+        )?;
+        for i in profiles {
+            writeln!(
+                file,
+                "{}\t{}\t{:.2}\t{}\t{}\t{}\t{}\t{}",
+                i.name,
+                i.length,
+                i.gc_percent(),
+                i.gc_count,
+                i.at_count,
+                i.n_count,
+                i.other_count,
+                i.gap_count
+            )?;
+        }
+        file.flush()
+    }
+
+    pub fn json_string(value: &str) -> String {
+        // A quoted JSON string, escaping what RFC 8259 requires
+        let mut quoted = String::with_capacity(value.len() + 2);
+        quoted.push('"');
+        for c in value.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                '\t' => quoted.push_str("\\t"),
+                c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    fn save_summary_json(
+        output: &str,
+        fasta_file: &str,
+        summary: &AssemblyProfile,
+    ) -> std::io::Result<()> {
+        // Hand written as the values are only numbers and file/sequence names,
+        // the names are escaped with json_string
+        let mut file = BufWriter::new(File::create(output)?);
+        writeln!(file, "{{")?;
+        writeln!(file, "  \"file\": {},", json_string(fasta_file))?;
+        writeln!(file, "  \"sequence_count\": {},", summary.sequence_count)?;
+        writeln!(file, "  \"total_length\": {},", summary.total_length)?;
+        writeln!(file, "  \"n50\": {},", summary.n50)?;
+        writeln!(file, "  \"l50\": {},", summary.l50)?;
+        writeln!(file, "  \"n90\": {},", summary.n90)?;
+        writeln!(file, "  \"l90\": {},", summary.l90)?;
+        writeln!(file, "  \"gc_percent\": {:.2},", summary.gc_percent)?;
+        writeln!(file, "  \"n_count\": {},", summary.n_count)?;
+        writeln!(file, "  \"n_percent\": {:.2},", summary.n_percent)?;
+        writeln!(file, "  \"gap_count\": {},", summary.gap_count)?;
+        writeln!(
+            file,
+            "  \"longest\": {{\"name\": {}, \"length\": {}}},",
+            json_string(&summary.longest.0),
+            summary.longest.1
+        )?;
+        writeln!(
+            file,
+            "  \"shortest\": {{\"name\": {}, \"length\": {}}}",
+            json_string(&summary.shortest.0),
+            summary.shortest.1
+        )?;
+        writeln!(file, "}}")?;
+        file.flush()
+    }
+
+    pub fn profile_to_dir(
+        fasta_file: &str,
+        outdir: &str,
+    ) -> Result<AssemblyProfile, Box<dyn Error>> {
+        // Profile the fasta and write the summary (txt + json)
+        // and per-sequence (tsv) files to outdir
+        let profiles = profile_fasta(fasta_file)?;
+        let summary = summarise(&profiles);
+
+        let prefix = Path::new(fasta_file)
+            .file_name()
+            .and_then(|x| x.to_str())
+            .and_then(|x| x.split('.').next())
+            .unwrap_or("profile");
+
+        fs::create_dir_all(outdir)?;
+        fs::write(
+            format!("{}/{}.summary.txt", outdir, prefix),
+            format!("{}\n", summary),
+        )?;
+        save_summary_json(
+            &format!("{}/{}.summary.json", outdir, prefix),
+            fasta_file,
+            &summary,
+        )?;
+        save_sequence_tsv(&format!("{}/{}.sequences.tsv", outdir, prefix), &profiles)?;
+
+        Ok(summary)
+    }
+
+    pub fn profile(arguments: std::option::Option<&ArgMatches>) {
+        let fasta_file: &String = arguments.unwrap().get_one::<String>("fasta-file").unwrap();
+        let outdir: &String = arguments.unwrap().get_one::<String>("output-dir").unwrap();
+
+        println!("Profiling fasta file: {}", fasta_file);
+
+        match profile_to_dir(fasta_file, outdir) {
+            Ok(summary) => {
+                println!("{}", summary);
+                println!(
+                    "{}\n{}\n\t{}",
+                    "FASTA HAS BEEN PROFILED".green(),
+                    "FOUND HERE:".green(),
+                    outdir.green()
+                );
+            }
+            Err(e) => panic!("Something is wrong with the file! | {}", e),
+        }
+    }
+}
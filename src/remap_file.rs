@@ -0,0 +1,301 @@
+pub mod remap_file_mod {
+    use clap::ArgMatches;
+    use colored::Colorize;
+    use noodles::{cram, sam};
+    use std::collections::{BTreeMap, HashMap};
+    use std::error::Error;
+    use std::fs::File;
+    use std::io::{BufRead, BufWriter, Write};
+
+    use crate::generics::open_reader;
+    use crate::remap_head::remapping_headers::pull_map_from_tsv;
+
+    #[derive(Debug, Clone, Default)]
+    pub struct NameMap {
+        map: HashMap<String, String>,
+        pub renamed: usize,
+        // Names not in the map and how many times they were seen
+        pub unmatched: BTreeMap<String, usize>,
+    }
+
+    impl NameMap {
+        pub fn new(pairs: impl IntoIterator<Item = (String, String)>) -> Result<NameMap, String> {
+            // A name going to more than one new name can't be renamed,
+            // e.g. forward through a merge of haplotypes
+            let mut targets: BTreeMap<String, Vec<String>> = BTreeMap::new();
+            for (from, to) in pairs {
+                targets.entry(from).or_default().push(to);
+            }
+            let ambiguous: Vec<String> = targets
+                .iter()
+                .filter(|x| x.1.len() > 1)
+                .map(|(from, to)| format!("{} ({})", from, to.join(", ")))
+                .collect();
+            if !ambiguous.is_empty() {
+                return Err(format!(
+                    "{} names map to more than one name: {}",
+                    ambiguous.len(),
+                    ambiguous.join(", ")
+                ));
+            }
+            Ok(NameMap {
+                map: targets
+                    .into_iter()
+                    .map(|(from, mut to)| (from, to.remove(0)))
+                    .collect(),
+                ..Default::default()
+            })
+        }
+
+        pub fn rename(&mut self, name: &str) -> String {
+            // Anything not in the map is left as it is
+            match self.map.get(name) {
+                Some(new) => {
+                    self.renamed += 1;
+                    new.to_owned()
+                }
+                None => {
+                    *self.unmatched.entry(name.to_string()).or_insert(0) += 1;
+                    name.to_string()
+                }
+            }
+        }
+    }
+
+    pub fn detect_format(path: &str) -> Option<String> {
+        // From the extension, ignoring a .gz
+        let lower = path.to_lowercase();
+        let lower = lower.trim_end_matches(".gz");
+        ["bed", "gff3", "gff", "paf", "agp", "tpf", "sam", "cram"]
+            .iter()
+            .find(|x| lower.ends_with(&format!(".{}", x)))
+            .map(|x| match *x {
+                "gff" => "gff3".to_string(),
+                x => x.to_string(),
+            })
+    }
+
+    fn rename_columns(line: &str, columns: &[usize], names: &mut NameMap) -> String {
+        let mut fields: Vec<String> = line.split('\t').map(|x| x.to_string()).collect();
+        for column in columns {
+            if let Some(field) = fields.get_mut(*column) {
+                *field = names.rename(field);
+            }
+        }
+        fields.join("\t")
+    }
+
+    pub fn remap_line(line: &str, format: &str, names: &mut NameMap) -> Result<String, String> {
+        //
+        // Only the sequence names are touched, everything else of the
+        // line is written back as it was
+        //
+        let fields: Vec<&str> = line.split('\t').collect();
+        let new_line = match format {
+            "bed" => rename_columns(line, &[0], names),
+            "gff3" => rename_columns(line, &[0], names),
+            // Query and target
+            "paf" => rename_columns(line, &[0, 5], names),
+            // The object, and the component unless it is a gap
+            "agp" => match fields.get(4) {
+                Some(&"N") | Some(&"U") => rename_columns(line, &[0], names),
+                _ => rename_columns(line, &[0, 5], names),
+            },
+            // ?    SCAFFOLD_1:1-9000   RL_1    PLUS
+            // split on any whitespace, as curate reads them
+            "tpf" => {
+                let columns: Vec<&str> = line.split_whitespace().collect();
+                match columns.get(1).and_then(|x| x.rsplit_once(':')) {
+                    _ if columns.first() == Some(&"GAP") => line.to_string(),
+                    Some((scaffold, range)) => {
+                        // Keep the spacing of the line, only the scaffold changes
+                        let after_first = line.find(columns[0]).unwrap() + columns[0].len();
+                        let at = after_first + line[after_first..].find(columns[1]).unwrap();
+                        format!(
+                            "{}{}:{}{}",
+                            &line[..at],
+                            names.rename(scaffold),
+                            range,
+                            &line[at + columns[1].len()..]
+                        )
+                    }
+                    None => return Err(format!("expected ? NAME:START-END in: {}", line)),
+                }
+            }
+            // RNAME and RNEXT, = and * are not names
+            "sam" => {
+                let mut fields: Vec<String> = fields.iter().map(|x| x.to_string()).collect();
+                for column in [2, 6] {
+                    if let Some(field) = fields.get_mut(column) {
+                        if field != "*" && field != "=" {
+                            *field = names.rename(field);
+                        }
+                    }
+                }
+                fields.join("\t")
+            }
+            _ => return Err(format!("{} is not a format remapfile knows", format)),
+        };
+        Ok(new_line)
+    }
+
+    pub fn remap_lines(
+        input: &mut dyn BufRead,
+        format: &str,
+        names: &mut NameMap,
+        out: &mut dyn Write,
+    ) -> Result<usize, Box<dyn Error>> {
+        //
+        // Stream the file through line by line, returning the number of lines.
+        // Comments are kept as they are, apart from those naming a sequence:
+        // GFF3 ##sequence-region and the fasta after ##FASTA, and SAM @SQ.
+        //
+        let mut in_fasta = false;
+        let mut count: usize = 0;
+        for (index, line) in input.lines().enumerate() {
+            let line = line?;
+            count += 1;
+            let new_line = if format == "gff3" && line.starts_with("##FASTA") {
+                in_fasta = true;
+                line
+            } else if in_fasta {
+                match line.strip_prefix('>') {
+                    Some(header) => {
+                        let (name, description) = match header.split_once(' ') {
+                            Some((name, description)) => (name, Some(description)),
+                            None => (header, None),
+                        };
+                        match description {
+                            Some(description) => {
+                                format!(">{} {}", names.rename(name), description)
+                            }
+                            None => format!(">{}", names.rename(name)),
+                        }
+                    }
+                    None => line,
+                }
+            } else if format == "gff3" && line.starts_with("##sequence-region") {
+                let mut fields: Vec<String> =
+                    line.split_whitespace().map(|x| x.to_string()).collect();
+                if let Some(field) = fields.get_mut(1) {
+                    *field = names.rename(field);
+                }
+                fields.join(" ")
+            } else if format == "sam" && line.starts_with("@SQ") {
+                line.split('\t')
+                    .map(|x| match x.strip_prefix("SN:") {
+                        Some(name) => format!("SN:{}", names.rename(name)),
+                        None => x.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\t")
+            } else if line.starts_with('#')
+                || line.starts_with('@')
+                || line.trim().is_empty()
+                || (format == "bed" && (line.starts_with("track") || line.starts_with("browser")))
+            {
+                line
+            } else {
+                remap_line(&line, format, names)
+                    .map_err(|e| format!("line {}: {}", index + 1, e))?
+            };
+            writeln!(out, "{}", new_line)?;
+        }
+        Ok(count)
+    }
+
+    pub fn remap_cram_header(
+        path: &str,
+        names: &mut NameMap,
+        out: &mut dyn Write,
+    ) -> Result<usize, Box<dyn Error>> {
+        // The CRAM itself can't be renamed without re-encoding it, the
+        // renamed header is written as SAM for samtools reheader
+        let mut reader = File::open(path).map(cram::io::Reader::new)?;
+        let header = reader.read_header()?;
+        let mut writer = sam::io::Writer::new(Vec::new());
+        writer.write_header(&header)?;
+        remap_lines(&mut writer.get_ref().as_slice(), "sam", names, out)
+    }
+
+    pub fn remap_file(arguments: std::option::Option<&ArgMatches>) {
+        let input: &String = arguments.unwrap().get_one::<String>("input").unwrap();
+        let map_file: &String = arguments.unwrap().get_one::<String>("map-file").unwrap();
+        let output: &String = arguments.unwrap().get_one::<String>("output").unwrap();
+        let direction: &String = arguments.unwrap().get_one::<String>("direction").unwrap();
+
+        let format = match arguments.unwrap().get_one::<String>("format") {
+            Some(format) if format != "auto" => format.to_owned(),
+            _ => match detect_format(input) {
+                Some(format) => format,
+                None => panic!(
+                    "Unable to tell the format of {} from its extension, use -F",
+                    input
+                ),
+            },
+        };
+
+        // The map is old -> new, back (the default) goes new -> old
+        let mapping = match pull_map_from_tsv(map_file) {
+            Ok(mapping) => mapping,
+            Err(e) => {
+                eprintln!("{}", format!("CAN'T READ MAPPING: {}", e).red());
+                std::process::exit(1);
+            }
+        };
+        let names = match direction.as_str() {
+            "forward" => NameMap::new(mapping.pairs),
+            _ => NameMap::new(mapping.pairs.into_iter().map(|(old, new)| (new, old))),
+        };
+        let mut names = match names {
+            Ok(names) => names,
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("CAN'T RENAME {} {}: {}", input, direction, e).red()
+                );
+                std::process::exit(1);
+            }
+        };
+
+        println!(
+            "Renaming {} ({}) {} with: {}",
+            input, format, direction, map_file
+        );
+
+        let mut out = BufWriter::new(File::create(output).expect("creation failed"));
+        let result = match format.as_str() {
+            "cram" => remap_cram_header(input, &mut names, &mut out),
+            _ => {
+                let mut reader = open_reader(input).expect("Unable to open input");
+                remap_lines(&mut reader, &format, &mut names, &mut out)
+            }
+        };
+        if let Err(e) = result.and_then(|_| Ok(out.flush()?)) {
+            eprintln!("{}", format!("CAN'T RENAME {}: {}", input, e).red());
+            std::process::exit(1);
+        }
+
+        println!(
+            "{}\n{}\n\t{}",
+            format!("{} NAMES CHANGED", names.renamed).green(),
+            "FOUND HERE:".green(),
+            output.green()
+        );
+        if format == "cram" {
+            println!(
+                "This is the renamed header, use: samtools reheader {} {}",
+                output, input
+            );
+        }
+        if !names.unmatched.is_empty() {
+            println!(
+                "{}",
+                format!("WARNING: {} NAMES NOT IN THE MAP:", names.unmatched.len()).yellow()
+            );
+            for (name, count) in &names.unmatched {
+                println!("\t{}\t{}", name.yellow(), count);
+            }
+        }
+    }
+}
@@ -1,41 +1,31 @@
 pub mod remapping_headers {
-    use crate::map_headers;
+    use crate::map_headers::mapping_headers::{
+        create_mapped_fasta, read_mapping, save_mapping, HeaderRewriter, Mapping, Unmapped,
+    };
     use clap::ArgMatches;
     use colored::Colorize;
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
-    use std::iter::Zip;
 
-    use crate::generics::validate_fasta;
+    use crate::generics::{md5_of, open_reader, output_compression};
 
-    pub fn pull_map_from_tsv(
-        map_file: &str,
-    ) -> Zip<std::vec::IntoIter<std::string::String>, std::vec::IntoIter<std::string::String>> {
-        let file_reader: File = File::open(map_file).expect("CAN'T OPEN FILE");
-        let buff_reader: BufReader<File> = BufReader::new(file_reader);
-
-        let mut old_head: Vec<String> = Vec::new();
-        let mut new_head: Vec<String> = Vec::new();
+    pub fn pull_map_from_tsv(map_file: &str) -> Result<Mapping, String> {
+        let mut reader = open_reader(map_file).map_err(|e| format!("{} | {}", map_file, e))?;
+        read_mapping(&mut reader).map_err(|e| format!("{} {}", map_file, e))
+    }
 
-        for line in buff_reader.lines() {
-            match line {
-                Ok(string) => {
-                    let mut old_new = string.split('\t');
-                    let x = old_new.next().unwrap();
-                    let y = old_new.next().unwrap();
-                    old_head.push(x.to_string());
-                    new_head.push(y.to_string());
-                }
-                Err(_) => {
-                    print!("")
-                }
-            };
+    pub fn check_mapped(mapping: &Mapping, fasta: &str) -> Result<(), String> {
+        // The fasta to remap should be the one the mapping was saved with
+        let (path, md5) = match mapping.header.as_ref().and_then(|x| x.mapped.as_ref()) {
+            Some(mapped) => mapped,
+            None => return Ok(()),
+        };
+        let checksum = md5_of(fasta).map_err(|e| format!("{} | {}", fasta, e))?;
+        if &checksum != md5 {
+            return Err(format!(
+                "{} (md5={}) is not the fasta made with this mapping, {} (md5={})",
+                fasta, checksum, path, md5
+            ));
         }
-
-        let mapped_heads: Zip<std::vec::IntoIter<String>, std::vec::IntoIter<String>> =
-            new_head.into_iter().zip(old_head);
-
-        mapped_heads
+        Ok(())
     }
 
     pub fn remapping_head(arguments: std::option::Option<&ArgMatches>) {
@@ -45,29 +35,113 @@ pub mod remapping_headers {
             .unwrap()
             .get_one::<String>("output-directory")
             .unwrap();
+        let keep_description: &bool = arguments
+            .unwrap()
+            .get_one::<bool>("keep-description")
+            .unwrap();
+        let unmapped: Unmapped = arguments
+            .unwrap()
+            .get_one::<String>("unmapped")
+            .unwrap()
+            .parse()
+            .unwrap();
+        let check_checksum: &bool = arguments
+            .unwrap()
+            .get_one::<bool>("check-checksum")
+            .unwrap();
+        let auto_prefix: &String = arguments.unwrap().get_one::<String>("auto-prefix").unwrap();
 
         println!("Mapping headers for file: {}", file);
         println!("Replace headers with string: {}", map_file);
 
-        match validate_fasta(file) {
-            Ok(_thing) => {
-                let new_map: Zip<std::vec::IntoIter<String>, std::vec::IntoIter<String>> =
-                    pull_map_from_tsv(map_file);
-
-                let new_fasta: String = format!("{output}_OH.fasta");
-
-                map_headers::mapping_headers::create_mapped_fasta(file, &new_fasta, new_map);
-
+        let mapping = match pull_map_from_tsv(map_file) {
+            Ok(mapping) => mapping,
+            Err(e) => {
+                eprintln!("{}", format!("CAN'T READ MAPPING: {}", e).red());
+                std::process::exit(1);
+            }
+        };
+        if *check_checksum {
+            if mapping.header.is_none() {
                 println!(
-                    "{}\n{}\n\t{}\n",
-                    "FASTA HAS BEEN RE-APPED AND REWRITTEN".green(),
-                    "FOUND HERE:".green(),
-                    &new_fasta.green()
+                    "{}",
+                    "WARNING: THE MAPPING HAS NO HEADER, THE FASTA CAN'T BE CHECKED AGAINST IT"
+                        .yellow()
                 );
+            } else if let Err(e) = check_mapped(&mapping, file) {
+                eprintln!("{}", format!("CHECKSUM MISMATCH: {}", e).red());
+                std::process::exit(1);
             }
-            Err(_) => {
-                println!("NOT A VALID FASTA")
-            }
+        }
+
+        if mapping.is_merge() {
+            // Each haplotype's SUPER_1 would go back to SUPER_1 in the one fasta
+            eprintln!(
+                "{}",
+                format!(
+                    "CAN'T REMAP HEADERS: {} IS FROM mergehaps, USE remapfile TO TAKE FILES BACK",
+                    map_file
+                )
+                .red()
+            );
+            std::process::exit(1);
+        }
+
+        // The mapping is old -> new, this goes back
+        let new_to_old = mapping.pairs.into_iter().map(|(old, new)| (new, old));
+        let mut rewriter = match HeaderRewriter::new(new_to_old) {
+            Ok(rewriter) => rewriter,
+            Err(e) => panic!("Something is wrong with the mapping! | {}", e),
         };
+        rewriter.keep_description = *keep_description;
+        rewriter.unmapped = unmapped;
+        rewriter.auto_prefix = auto_prefix.to_owned();
+
+        let compression =
+            output_compression(output, arguments.unwrap().get_one::<String>("compress"));
+        let new_fasta: String = format!("{output}_OH.fasta{}", compression.suffix());
+
+        let (summary, header) =
+            match create_mapped_fasta(file, &new_fasta, &mut rewriter, compression) {
+                Ok(mapped) => mapped,
+                Err(e) => {
+                    eprintln!("{}", format!("CAN'T REMAP HEADERS: {}", e).red());
+                    std::process::exit(1);
+                }
+            };
+
+        println!(
+            "{}\n{}\n\t{}",
+            "FASTA HAS BEEN RE-MAPPED AND REWRITTEN".green(),
+            "FOUND HERE:".green(),
+            &new_fasta.green()
+        );
+        if !summary.kept.is_empty() {
+            println!(
+                "{}",
+                format!(
+                    "WARNING: {} HEADERS NOT IN THE MAPPING WERE KEPT:",
+                    summary.kept.len()
+                )
+                .yellow()
+            );
+            for name in &summary.kept {
+                println!("\t{}", name.yellow());
+            }
+        }
+        if !summary.assigned.is_empty() {
+            // In the mapped-heads.tsv layout, so they can be taken back too
+            let added_file = format!("{output}_OH.added-heads.tsv");
+            println!(
+                "{}\n\t{}",
+                format!(
+                    "WARNING: {} HEADERS NOT IN THE MAPPING WERE RENAMED, SEE:",
+                    summary.assigned.len()
+                )
+                .yellow(),
+                added_file.yellow()
+            );
+            save_mapping(&added_file, summary.assigned, &header);
+        }
     }
 }
@@ -1,10 +1,10 @@
 pub mod split_by_count_mod {
-    use crate::generics::{sanitise_header, write_fasta};
+    use crate::generics::{open_fasta, output_compression, sanitise_header, write_fasta};
     use clap::ArgMatches;
     use compare::{natural, Compare};
     use noodles::fasta::{self, Record};
     use std::cmp::Ordering;
-    use std::{fs::File, io::BufReader, path::Path};
+    use std::path::Path;
 
     #[allow(clippy::needless_return)]
     fn fix_head(records: Record, sanitise: bool) -> Record {
@@ -37,6 +37,7 @@ pub mod split_by_count_mod {
 
         let new_outpath = format!("{}/{}/{}/", outpath, actual_name, data_type);
         let fasta_count = arguments.unwrap().get_one::<u16>("count").unwrap();
+        let compress = arguments.unwrap().get_one::<String>("compress");
         println!(
             "Fasta file for processing: {:?}\nNumber of records per file: {:?}",
             fasta_file, fasta_count
@@ -50,10 +51,7 @@ pub mod split_by_count_mod {
         let file_name: Vec<&str> = actual_name.split('.').collect();
 
         // Open the fasta file
-        let mut reader = File::open(fasta_file)
-            .map(BufReader::new)
-            .map(fasta::Reader::new)
-            .unwrap();
+        let mut reader = open_fasta(fasta_file).unwrap();
 
         // Create a Record List
         let mut record_list: Vec<Record> = Vec::new();
@@ -75,7 +73,10 @@ pub mod split_by_count_mod {
                     &record_list.len()
                 );
 
-                let _ = write_fasta(&new_outpath, file_name, record_list);
+                let compression = output_compression(&file_name, compress);
+                if let Err(e) = write_fasta(&new_outpath, file_name, record_list, compression) {
+                    panic!("Something is wrong with the file! | {}", e)
+                }
                 file_counter += 1;
                 counter = 0;
                 record_list = Vec::new();
@@ -89,6 +90,9 @@ pub mod split_by_count_mod {
             &fasta_count,
             &record_list.len()
         );
-        let _ = write_fasta(&new_outpath, file_name, record_list);
+        let compression = output_compression(&file_name, compress);
+        if let Err(e) = write_fasta(&new_outpath, file_name, record_list, compression) {
+            panic!("Something is wrong with the file! | {}", e)
+        }
     }
 }
@@ -1,5 +1,7 @@
 pub mod split_by_size_mod {
-    use crate::generics::{only_keys, validate_fasta, write_fasta};
+    use crate::generics::{
+        only_keys, open_indexed_fasta, output_compression, validate_fasta, write_fasta,
+    };
     use clap::ArgMatches;
     use noodles::fasta;
     use noodles::fasta::record::Definition;
@@ -77,6 +79,7 @@ pub mod split_by_size_mod {
         let actual_name = actual_list[0];
 
         let new_outpath = format!("{}/{}/{}/", outpath, actual_name, data_type);
+        let compress = arguments.unwrap().get_one::<String>("compress");
 
         println!("Fasta file for processing: {:?}", &fasta_file);
         println!("Size to chunk fasta into: {:?}", &chunk_size);
@@ -89,9 +92,8 @@ pub mod split_by_size_mod {
         // Returns only the HashMap< usize, Hashmap<String, usize>>
         let split_hash = find_chunks(&results, chunk_size);
 
-        // Duplicated from TPF_FASTA
-        // Should be abstracted into generics
-        let reader = fasta::indexed_reader::Builder::default().build_from_path(fasta_file);
+        // Needs the .fai, and the .gzi if the fasta is bgzipped
+        let reader = open_indexed_fasta(fasta_file);
         let fasta_repo = match reader {
             Ok(data) => {
                 let adapter = IndexedReader::new(data);
@@ -99,7 +101,10 @@ pub mod split_by_size_mod {
                 // Now read the fasta and return is as a queryable object
                 fasta::Repository::new(adapter)
             }
-            Err(_) => todo!(), // Probably just panic!
+            Err(e) => panic!(
+                "Can't open {} for indexed reading, is there a .fai (and .gzi if bgzipped)? | {}",
+                fasta_file, e
+            ),
         };
 
         for i in split_hash {
@@ -118,7 +123,10 @@ pub mod split_by_size_mod {
             }
             let file_name = format!("{}_f{}_{}.fasta", actual_name, i.0, data_type);
 
-            let _ = write_fasta(&new_outpath, file_name, record_list);
+            let compression = output_compression(&file_name, compress);
+            if let Err(e) = write_fasta(&new_outpath, file_name, record_list, compression) {
+                panic!("Something is wrong with the file! | {}", e)
+            }
         }
         //println!("{:?}", split_hash)
     }
@@ -0,0 +1,186 @@
+pub mod subset_mod {
+    use clap::ArgMatches;
+    use colored::Colorize;
+    use noodles::fasta;
+    use std::error::Error;
+    use std::io::BufRead;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use crate::generics::{output_compression, Compression, FastaOutput};
+
+    // SplitMix64, small and good enough for sampling.
+    // Kept in house so that a seed gives the same subset
+    // regardless of any crate version.
+    pub struct SubsetRng {
+        state: u64,
+    }
+
+    impl SubsetRng {
+        pub fn new(seed: u64) -> SubsetRng {
+            SubsetRng { state: seed }
+        }
+
+        pub fn next_u64(&mut self) -> u64 {
+            self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = self.state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        }
+
+        pub fn below(&mut self, bound: usize) -> usize {
+            // Value in 0..bound
+            (self.next_u64() % bound as u64) as usize
+        }
+    }
+
+    fn target_of(total: usize, percent: u16) -> usize {
+        // Rounded up so that any non-zero percent keeps something
+        (total * percent as usize).div_ceil(100)
+    }
+
+    pub fn select_head(lengths: &[usize], percent: u16, by_bases: bool) -> Vec<bool> {
+        // Keep records from the top of the file until the
+        // requested percent of records (or bases) is reached
+        let mut selected = vec![false; lengths.len()];
+        let total = if by_bases {
+            lengths.iter().sum()
+        } else {
+            lengths.len()
+        };
+        let target = target_of(total, percent);
+
+        let mut kept: usize = 0;
+        for (index, length) in lengths.iter().enumerate() {
+            if kept >= target {
+                break;
+            }
+            selected[index] = true;
+            kept += if by_bases { *length } else { 1 };
+        }
+        selected
+    }
+
+    pub fn select_random(lengths: &[usize], percent: u16, by_bases: bool, seed: u64) -> Vec<bool> {
+        // Seeded random choice of records, the output keeps file order
+        let mut rng = SubsetRng::new(seed);
+        let mut selected = vec![false; lengths.len()];
+
+        if by_bases {
+            // Shuffle the record order and keep records until
+            // the base target is met
+            let target = target_of(lengths.iter().sum(), percent);
+            let mut order: Vec<usize> = (0..lengths.len()).collect();
+            for i in (1..order.len()).rev() {
+                order.swap(i, rng.below(i + 1));
+            }
+
+            let mut kept: usize = 0;
+            for index in order {
+                if kept >= target {
+                    break;
+                }
+                selected[index] = true;
+                kept += lengths[index];
+            }
+        } else {
+            // Selection sampling (Knuth Algorithm S), gives exactly
+            // target records in a single pass
+            let target = target_of(lengths.len(), percent);
+            let mut needed = target;
+            for (index, choice) in selected.iter_mut().enumerate() {
+                let remaining = lengths.len() - index;
+                if rng.below(remaining) < needed {
+                    *choice = true;
+                    needed -= 1;
+                }
+            }
+        }
+        selected
+    }
+
+    pub fn record_lengths(path: &str) -> Result<Vec<usize>, Box<dyn Error>> {
+        // Lengths of each record in file order, the sequences
+        // themselves are dropped as soon as they are read
+        let reader: Result<fasta::Reader<Box<dyn BufRead>>, std::io::Error> =
+            crate::generics::open_fasta(path);
+
+        match reader {
+            Ok(mut binding) => {
+                let mut lengths = Vec::new();
+                for result in binding.records() {
+                    lengths.push(result?.sequence().len());
+                }
+                Ok(lengths)
+            }
+            Err(_) => Err("Error: Fasta is not valid check file!".into()),
+        }
+    }
+
+    pub fn write_subset(
+        input: &str,
+        output: &str,
+        selected: &[bool],
+        compression: Compression,
+    ) -> Result<usize, Box<dyn Error>> {
+        // Second pass through the fasta, streaming selected records to output
+        let mut reader: fasta::Reader<Box<dyn BufRead>> = crate::generics::open_fasta(input)?;
+        let mut file = FastaOutput::create(output, compression)?;
+        let mut writer = fasta::Writer::new(&mut file);
+
+        let mut written: usize = 0;
+        for (result, keep) in reader.records().zip(selected) {
+            let record = result?;
+            if *keep {
+                writer.write_record(&record)?;
+                written += 1;
+            }
+        }
+        file.finish()?;
+        Ok(written)
+    }
+
+    pub fn subset(arguments: std::option::Option<&ArgMatches>) {
+        let fasta_file: &String = arguments.unwrap().get_one::<String>("fasta-file").unwrap();
+        let random: &bool = arguments.unwrap().get_one::<bool>("random").unwrap();
+        let percent: &u16 = arguments.unwrap().get_one::<u16>("percent").unwrap();
+        let by_bases: bool = arguments.unwrap().get_one::<String>("by").unwrap() == "bases";
+        let output: &String = arguments.unwrap().get_one::<String>("output").unwrap();
+
+        println!("Subsetting fasta file: {}", fasta_file);
+
+        let lengths = match record_lengths(fasta_file) {
+            Ok(lengths) => lengths,
+            Err(e) => panic!("Something is wrong with the file! | {}", e),
+        };
+
+        let selected = if *random {
+            let seed = match arguments.unwrap().get_one::<u64>("seed") {
+                Some(seed) => *seed,
+                None => SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .expect("Time went backwards")
+                    .as_nanos() as u64,
+            };
+            // Print the seed so the subset can be regenerated with --seed
+            println!("Random subset using seed: {}", seed.to_string().bold());
+            select_random(&lengths, *percent, by_bases, seed)
+        } else {
+            select_head(&lengths, *percent, by_bases)
+        };
+
+        let compression =
+            output_compression(output, arguments.unwrap().get_one::<String>("compress"));
+        match write_subset(fasta_file, output, &selected, compression) {
+            Ok(written) => println!(
+                "{}\n{}\n\t{} ({} of {} records)",
+                "FASTA HAS BEEN SUBSET".green(),
+                "FOUND HERE:".green(),
+                output.green(),
+                written,
+                lengths.len()
+            ),
+            Err(e) => panic!("Something is wrong with the file! | {}", e),
+        }
+    }
+}
@@ -1,22 +1,31 @@
 pub mod tpf_fasta_mod {
     use clap::ArgMatches;
-    use noodles::core::Position;
+    use colored::Colorize;
+    use noodles::core::{Position, Region};
     use noodles::fasta;
-    use noodles::fasta::record::Sequence;
-    use noodles::fasta::repository::adapters::IndexedReader;
-    use std::fs::OpenOptions;
-    use std::io::Write;
+    use std::cmp::Ordering;
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::io::{BufRead, BufWriter, Seek, Write};
+    use std::path::Path;
     use std::{fs::read_to_string, fs::File, str};
 
-    use crate::generics::validate_fasta;
+    use crate::curation_report::curation_report_mod::curation_report;
+    use crate::generics::{
+        detect_compression, ensure_fai, open_fasta_with_index, output_compression, validate_fasta,
+        Compression, FastaOutput,
+    };
+    use crate::naming::naming_mod::{read_assignments, NamingScheme};
 
-    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub struct Tpf {
         pub ori_scaffold: String,
         pub start_coord: usize,
         pub end_coord: usize,
         pub new_scaffold: String,
         pub orientation: String,
+        // AGP component type, W for a TPF
+        pub component_type: char,
     }
 
     impl std::fmt::Display for Tpf {
@@ -30,116 +39,407 @@ pub mod tpf_fasta_mod {
         }
     }
 
-    #[derive(Debug, PartialEq, Eq)]
-    pub struct NewFasta {
-        pub tpf: Tpf,
-        pub sequence: String,
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Gap {
+        pub new_scaffold: String,
+        // AGP component type, N for a gap of known size, U for unknown
+        pub component_type: char,
+        // None when no size is given, n_length is used instead
+        pub length: Option<usize>,
+        pub gap_type: String,
+        pub linkage_evidence: String,
+    }
+
+    impl Gap {
+        pub fn default_gap(new_scaffold: &str, n_length: usize) -> Gap {
+            // The gap used to join components when the input doesn't say otherwise
+            Gap {
+                new_scaffold: new_scaffold.to_owned(),
+                component_type: 'N',
+                length: Some(n_length),
+                gap_type: "scaffold".to_string(),
+                linkage_evidence: "proximity_ligation".to_string(),
+            }
+        }
+
+        pub fn resolved_length(&self, n_length: usize) -> usize {
+            self.length.unwrap_or(n_length)
+        }
+    }
+
+    // A TPF or AGP line, either a piece of the original
+    // assembly or a gap between two of them
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum TpfEntry {
+        Component(Tpf),
+        Gap(Gap),
+    }
+
+    impl TpfEntry {
+        pub fn new_scaffold(&self) -> &String {
+            match self {
+                TpfEntry::Component(tpf) => &tpf.new_scaffold,
+                TpfEntry::Gap(gap) => &gap.new_scaffold,
+            }
+        }
+
+        pub fn length(&self, n_length: usize) -> usize {
+            // Bases this entry adds to its new scaffold
+            match self {
+                TpfEntry::Component(tpf) => tpf.end_coord + 1 - tpf.start_coord,
+                TpfEntry::Gap(gap) => gap.resolved_length(n_length),
+            }
+        }
+    }
+
+    pub fn tpf_gap(new_scaffold: &str, tpf_type: &str, length: Option<usize>) -> Gap {
+        //
+        // Translate a TPF gap into its AGP terms
+        // TYPE-3 gaps are within a contig, anything else is a
+        // join made in curation and so supported by Hi-C
+        //
+        let (gap_type, linkage_evidence) = match tpf_type {
+            "TYPE-3" => ("contig", "na"),
+            _ => ("scaffold", "proximity_ligation"),
+        };
+        Gap {
+            new_scaffold: new_scaffold.to_owned(),
+            component_type: if length.is_some() { 'N' } else { 'U' },
+            length,
+            gap_type: gap_type.to_string(),
+            linkage_evidence: linkage_evidence.to_string(),
+        }
+    }
+
+    fn line_error(line_number: usize, message: String) -> Box<dyn Error> {
+        format!("line {}: {}", line_number, message).into()
+    }
+
+    fn parse_coord(line_number: usize, value: &str, line: &str) -> Result<usize, Box<dyn Error>> {
+        value.trim().parse::<usize>().map_err(|_| {
+            line_error(
+                line_number,
+                format!("'{}' is not a valid coordinate in: {}", value, line),
+            )
+        })
     }
 
-    #[derive(Debug)]
-    struct MyRecord {
-        name: String,
-        sequence: Vec<String>,
+    fn parse_tpf_component(line_number: usize, line: &str) -> Result<Tpf, Box<dyn Error>> {
+        // ?    SCAFFOLD_1:1-9    RL_1    MINUS
+        let line_replaced = line.replace('\t', " ");
+        let line_list: Vec<&str> = line_replaced.split_whitespace().collect();
+        if line_list.len() < 4 {
+            return Err(line_error(
+                line_number,
+                format!(
+                    "expected 4 columns (? SCAFFOLD:START-END NEW_SCAFFOLD ORIENTATION) in: {}",
+                    line
+                ),
+            ));
+        }
+
+        let (ori_scaffold, coords) = line_list[1].rsplit_once(':').ok_or_else(|| {
+            line_error(
+                line_number,
+                format!("'{}' is not in the SCAFFOLD:START-END format", line_list[1]),
+            )
+        })?;
+        let (start, end) = coords.split_once('-').ok_or_else(|| {
+            line_error(
+                line_number,
+                format!("'{}' is not in the SCAFFOLD:START-END format", line_list[1]),
+            )
+        })?;
+        let start_coord = parse_coord(line_number, start, line)?;
+        let end_coord = parse_coord(line_number, end, line)?;
+        if start_coord == 0 || start_coord > end_coord {
+            return Err(line_error(
+                line_number,
+                format!(
+                    "{}-{} is not a valid range, coordinates are 1-based and START <= END",
+                    start_coord, end_coord
+                ),
+            ));
+        }
+
+        let orientation = line_list[3].to_owned();
+        if orientation != "PLUS" && orientation != "MINUS" {
+            return Err(line_error(
+                line_number,
+                format!("orientation should be PLUS or MINUS not '{}'", orientation),
+            ));
+        }
+
+        Ok(Tpf {
+            ori_scaffold: ori_scaffold.to_owned(),
+            start_coord,
+            end_coord,
+            new_scaffold: line_list[2].to_owned(),
+            orientation,
+            component_type: 'W',
+        })
     }
 
-    pub fn parse_tpf(path: &String) -> Vec<Tpf> {
-        // Instantiate a List of Tpf objects
-        let mut all_tpf: Vec<Tpf> = Vec::new();
-        for line in read_to_string(path).unwrap().lines() {
-            // If line starts with '?' parse line, lines
-            // without this are gaps
+    pub fn read_tpf(path: &String) -> Result<Vec<(usize, TpfEntry)>, Box<dyn Error>> {
+        //
+        // Parse both the components ('?' lines) and the gaps
+        // ('GAP TYPE-2 200' lines) of a TPF, each with the line
+        // number it came from.
+        // A gap belongs to the scaffold either side of it, gaps at the
+        // start or end of a scaffold join nothing and are dropped.
+        // If a scaffold is picked up again further down the TPF it is
+        // rejoined with a gap of unknown size.
+        //
+        let mut all_entries: Vec<(usize, TpfEntry)> = Vec::new();
+        let mut pending_gap: Option<(usize, String, Option<usize>)> = None;
+        let mut seen: Vec<String> = Vec::new();
+
+        for (index, line) in read_to_string(path)?.lines().enumerate() {
+            let line_number = index + 1;
+
             if line.starts_with('?') {
-                // Parse data into TpF object
+                let data = parse_tpf_component(line_number, line)?;
+
+                let previous = all_entries.last().map(|x| x.1.new_scaffold().to_owned());
+                if previous.as_ref() == Some(&data.new_scaffold) {
+                    if let Some((gap_line, tpf_type, length)) = pending_gap.take() {
+                        all_entries.push((
+                            gap_line,
+                            TpfEntry::Gap(tpf_gap(&data.new_scaffold, &tpf_type, length)),
+                        ));
+                    }
+                } else if seen.contains(&data.new_scaffold) {
+                    all_entries.push((
+                        line_number,
+                        TpfEntry::Gap(tpf_gap(&data.new_scaffold, "", None)),
+                    ));
+                }
+
+                if !seen.contains(&data.new_scaffold) {
+                    seen.push(data.new_scaffold.to_owned());
+                }
+                pending_gap = None;
+                all_entries.push((line_number, TpfEntry::Component(data)));
+            } else if line.starts_with("GAP") {
+                // GAP TYPE-2 200, the size may be missing
                 let line_replaced = line.replace('\t', " ");
                 let line_list: Vec<&str> = line_replaced.split_whitespace().collect();
-                let scaff_data: Vec<&str> = line_list[1].split(':').collect();
-                let scaff_coords: Vec<&str> = scaff_data[1].split('-').collect();
-                let data = Tpf {
-                    ori_scaffold: scaff_data[0].to_owned(),
-                    start_coord: scaff_coords[0].to_owned().parse::<usize>().unwrap(),
-                    end_coord: scaff_coords[1].to_owned().parse::<usize>().unwrap(),
-                    new_scaffold: line_list[2].to_owned().replace("RL", "SUPER"),
-                    orientation: line_list[3].to_owned(),
+                let length = match line_list.get(2) {
+                    Some(size) => Some(parse_coord(line_number, size, line)?),
+                    None => None,
                 };
-                all_tpf.push(data);
+                pending_gap = Some((
+                    line_number,
+                    line_list.get(1).unwrap_or(&"").to_string(),
+                    length,
+                ));
             }
         }
-        all_tpf
+        Ok(all_entries)
+    }
+
+    pub fn parse_tpf_entries(path: &String) -> Vec<TpfEntry> {
+        // As read_tpf, without the line numbers and with the
+        // default RL_1 -> SUPER_1 naming
+        match read_tpf(path).and_then(|entries| {
+            apply_naming(
+                entries.into_iter().map(|x| x.1).collect(),
+                &NamingScheme::default(),
+            )
+        }) {
+            Ok(entries) => entries,
+            Err(e) => panic!("Something is wrong with the TPF! | {}", e),
+        }
     }
 
-    pub fn subset_vec_tpf<'a>(
-        tpf: &'a Vec<Tpf>,
-        fasta: (&std::string::String, &usize),
-    ) -> Vec<&'a Tpf> {
+    // AGP 2.1 component types which are sequence, A active finishing,
+    // D draft, F finished, G whole genome finishing, O other, P pre draft
+    // and W WGS contig. N and U are gaps
+    pub const AGP_SEQUENCE_TYPES: [&str; 7] = ["A", "D", "F", "G", "O", "P", "W"];
+
+    pub fn is_agp(path: &String) -> bool {
+        // An AGP either says so in the extension or has an AGP
+        // component type in column 5 of the first data line
+        if path.to_lowercase().ends_with(".agp") {
+            return true;
+        }
+        match read_to_string(path) {
+            Ok(data) => data
+                .lines()
+                .find(|line| !line.starts_with('#') && !line.trim().is_empty())
+                .map(|line| {
+                    let columns: Vec<&str> = line.split('\t').collect();
+                    columns.len() >= 9
+                        && (AGP_SEQUENCE_TYPES.contains(&columns[4])
+                            || ["N", "U"].contains(&columns[4]))
+                        && !line.starts_with('?')
+                })
+                .unwrap_or(false),
+            Err(_) => false,
+        }
+    }
+
+    pub fn read_agp(path: &String) -> Result<Vec<(usize, TpfEntry)>, Box<dyn Error>> {
         //
-        // Subset the Vec<TPF> based on a search through the fasta
+        // Parse an AGP 2.1 file into the same model as a TPF
+        // A, D, F, G, O, P and W lines are components, N and U lines are gaps which keep
+        // the length, gap type and linkage evidence given in the AGP
         //
-        let mut subset_tpf: Vec<&Tpf> = Vec::new();
-        for i in tpf {
-            if i.ori_scaffold == *fasta.0 {
-                subset_tpf.push(i)
+        let mut all_entries: Vec<(usize, TpfEntry)> = Vec::new();
+        for (index, line) in read_to_string(path)?.lines().enumerate() {
+            let line_number = index + 1;
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let columns: Vec<&str> = line.split('\t').collect();
+            if columns.len() < 9 {
+                return Err(line_error(
+                    line_number,
+                    format!("expected 9 tab separated columns in: {}", line),
+                ));
+            }
+            match columns[4] {
+                "N" | "U" => all_entries.push((
+                    line_number,
+                    TpfEntry::Gap(Gap {
+                        new_scaffold: columns[0].to_owned(),
+                        component_type: columns[4].chars().next().unwrap(),
+                        length: Some(parse_coord(line_number, columns[5], line)?),
+                        gap_type: columns[6].to_owned(),
+                        linkage_evidence: columns[8].to_owned(),
+                    }),
+                )),
+                component_type if AGP_SEQUENCE_TYPES.contains(&component_type) => {
+                    let start_coord = parse_coord(line_number, columns[6], line)?;
+                    let end_coord = parse_coord(line_number, columns[7], line)?;
+                    if start_coord == 0 || start_coord > end_coord {
+                        return Err(line_error(
+                            line_number,
+                            format!(
+                                "{}-{} is not a valid range, coordinates are 1-based and START <= END",
+                                start_coord, end_coord
+                            ),
+                        ));
+                    }
+                    all_entries.push((
+                        line_number,
+                        TpfEntry::Component(Tpf {
+                            ori_scaffold: columns[5].to_owned(),
+                            start_coord,
+                            end_coord,
+                            new_scaffold: columns[0].to_owned(),
+                            // ?, 0 and na are all unknown orientation, treated as PLUS
+                            orientation: match columns[8] {
+                                "-" => "MINUS".to_string(),
+                                _ => "PLUS".to_string(),
+                            },
+                            component_type: columns[4].chars().next().unwrap(),
+                        }),
+                    ))
+                }
+                other => {
+                    return Err(line_error(
+                        line_number,
+                        format!(
+                            "unsupported component type '{}', expected one of {}, N or U",
+                            other,
+                            AGP_SEQUENCE_TYPES.join(", ")
+                        ),
+                    ))
+                }
             }
         }
-        subset_tpf
+        Ok(all_entries)
     }
 
-    // The TPF will contain data in both PLUS (normal) and
-    // MINUS (inverted), if MINUS then we need to invert again
-    // and get the complement sequence
-    // We then return the sequence of the record.
-    pub fn check_orientation(
-        parsed: std::option::Option<noodles::fasta::record::Sequence>,
-        orientation: String,
-    ) -> String {
-        if orientation == "MINUS" {
-            let start = Position::try_from(1).unwrap();
-            let parse_orientation = parsed.unwrap();
-            let compliment: Sequence = parse_orientation
-                .complement()
-                .collect::<Result<_, _>>()
-                .unwrap();
-            let seq = compliment.get(start..).unwrap();
-            str::from_utf8(seq).unwrap().chars().rev().collect()
-        } else {
-            let start = Position::try_from(1).unwrap();
-            let parse_orientation = parsed.unwrap();
-            let seq = parse_orientation.get(start..).unwrap();
-            str::from_utf8(seq).unwrap().chars().collect()
+    pub fn gapped_tpf(tpf_data: Vec<Tpf>, n_length: usize) -> Vec<TpfEntry> {
+        //
+        // Group the TPF components by new scaffold and join the
+        // components of each with a gap of n_length
+        //
+        let mut all_entries: Vec<TpfEntry> = Vec::new();
+        for scaffold in get_uniques(&tpf_data) {
+            let mut first = true;
+            for tpf in tpf_data.iter().filter(|x| x.new_scaffold == scaffold) {
+                if !first {
+                    all_entries.push(TpfEntry::Gap(Gap::default_gap(&scaffold, n_length)));
+                }
+                all_entries.push(TpfEntry::Component(tpf.to_owned()));
+                first = false;
+            }
         }
+        all_entries
     }
 
-    pub fn parse_seq(
-        sequence: std::option::Option<noodles::fasta::record::Sequence>,
-        tpf: Vec<&Tpf>,
-    ) -> Vec<NewFasta> {
-        //
-        // Take the input sequence and scaffold name
-        // Parse the input sequence based on the data contained in
-        // the TPF. Which is already a subset based on scaff name.
+    pub fn components(entries: &[TpfEntry]) -> Vec<Tpf> {
+        // Only the pieces of the original assembly, no gaps
+        entries
+            .iter()
+            .filter_map(|x| match x {
+                TpfEntry::Component(tpf) => Some(tpf.to_owned()),
+                TpfEntry::Gap(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn complement_base(base: u8) -> Option<u8> {
         //
-        // for instance this Vec may only contain SCAFFOLD_1 TPF records
-        // if the sequence is from a SCAFFOLD_1 component
-        // as we move through the list, we are cutting the sequence at the
-        // recorded positions and outputting the new sequence.
+        // Complement of a single IUPAC base, keeping the case
+        // so that soft-masked (lowercase) repeats stay masked.
+        // S, W, N and gap characters are their own complement
         //
-        let mut subset_tpf: Vec<NewFasta> = Vec::new();
-
-        let new_seq = sequence.unwrap(); // Option(Sequence ()) -> Sequence ()
-        for &i in &tpf {
-            let start = Position::try_from(i.start_coord).unwrap();
-            let end = Position::try_from(i.end_coord).unwrap();
-            let parsed = new_seq.slice(start..=end);
-            let the_sequence = check_orientation(parsed, i.orientation.to_owned());
-            let data = NewFasta {
-                tpf: i.to_owned(),
-                sequence: the_sequence,
-            };
-            subset_tpf.push(data);
+        let complement = match base.to_ascii_uppercase() {
+            b'A' => b'T',
+            b'T' | b'U' => b'A',
+            b'C' => b'G',
+            b'G' => b'C',
+            b'R' => b'Y',
+            b'Y' => b'R',
+            b'K' => b'M',
+            b'M' => b'K',
+            b'B' => b'V',
+            b'V' => b'B',
+            b'D' => b'H',
+            b'H' => b'D',
+            b'S' | b'W' | b'N' | b'-' | b'.' | b'*' => base.to_ascii_uppercase(),
+            _ => return None,
+        };
+        if base.is_ascii_lowercase() {
+            Some(complement.to_ascii_lowercase())
+        } else {
+            Some(complement)
+        }
+    }
+
+    pub fn reverse_complement(sequence: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        // Errors with the 1-based position of the first base we don't know
+        sequence
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, base)| {
+                complement_base(*base).ok_or_else(|| {
+                    format!(
+                        "Unexpected character '{}' at position {}, not an IUPAC base",
+                        base.escape_ascii(),
+                        index + 1
+                    )
+                    .into()
+                })
+            })
+            .collect()
+    }
+
+    pub fn orient(sequence: &[u8], orientation: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        // MINUS components are reverse complemented, PLUS left as they are
+        if orientation == "MINUS" {
+            reverse_complement(sequence)
+        } else {
+            Ok(sequence.to_vec())
         }
-        subset_tpf
     }
 
-    pub fn get_uniques(tpf_list: &Vec<Tpf>) -> Vec<String> {
+    fn get_uniques(tpf_list: &[Tpf]) -> Vec<String> {
         // Get a Vec of the uniques names in the TPF Vec
         let mut uniques: Vec<String> = Vec::new();
 
@@ -151,100 +451,672 @@ pub mod tpf_fasta_mod {
         uniques
     }
 
-    // The function could take in a path where the output files are stored.
-    pub fn save_to_fasta(
-        fasta_data: Vec<NewFasta>,
-        tpf_data: Vec<Tpf>,
-        output: &String,
+    pub fn group_by_scaffold(entries: &[TpfEntry]) -> Vec<(String, Vec<&TpfEntry>)> {
+        //
+        // Collect the entries of each new scaffold, scaffolds are in the
+        // order they first appear. Single pass so this stays linear in
+        // the size of the TPF.
+        //
+        let mut groups: Vec<(String, Vec<&TpfEntry>)> = Vec::new();
+        let mut index: HashMap<&String, usize> = HashMap::new();
+        for entry in entries {
+            match index.get(entry.new_scaffold()) {
+                Some(i) => groups[*i].1.push(entry),
+                None => {
+                    index.insert(entry.new_scaffold(), groups.len());
+                    groups.push((entry.new_scaffold().to_owned(), vec![entry]));
+                }
+            }
+        }
+        groups
+    }
+
+    pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+        // Compare names so that runs of digits are compared by value
+        // e.g. SUPER_2 < SUPER_10
+        let mut a_chars = a.chars().peekable();
+        let mut b_chars = b.chars().peekable();
+
+        loop {
+            match (a_chars.peek(), b_chars.peek()) {
+                (None, None) => return Ordering::Equal,
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                    let mut a_num = String::new();
+                    while let Some(c) = a_chars.next_if(|c| c.is_ascii_digit()) {
+                        a_num.push(c);
+                    }
+                    let mut b_num = String::new();
+                    while let Some(c) = b_chars.next_if(|c| c.is_ascii_digit()) {
+                        b_num.push(c);
+                    }
+                    // Leading zeros don't change the value, then the longer
+                    // number is the larger
+                    let a_trim = a_num.trim_start_matches('0');
+                    let b_trim = b_num.trim_start_matches('0');
+                    let ordering = a_trim
+                        .len()
+                        .cmp(&b_trim.len())
+                        .then_with(|| a_trim.cmp(b_trim))
+                        .then_with(|| a_num.len().cmp(&b_num.len()));
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                }
+                (Some(x), Some(y)) => {
+                    let ordering = x.cmp(y);
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                    a_chars.next();
+                    b_chars.next();
+                }
+            }
+        }
+    }
+
+    pub fn apply_naming(
+        entries: Vec<TpfEntry>,
+        naming: &NamingScheme,
+    ) -> Result<Vec<TpfEntry>, Box<dyn Error>> {
+        // Give every component and gap its new scaffold name
+        let renamed = naming.rename_all(&scaffold_order(&entries))?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| match entry {
+                TpfEntry::Component(mut tpf) => {
+                    tpf.new_scaffold = renamed[&tpf.new_scaffold].to_owned();
+                    TpfEntry::Component(tpf)
+                }
+                TpfEntry::Gap(mut gap) => {
+                    gap.new_scaffold = renamed[&gap.new_scaffold].to_owned();
+                    TpfEntry::Gap(gap)
+                }
+            })
+            .collect())
+    }
+
+    pub fn sort_entries(
+        entries: Vec<TpfEntry>,
+        sort: &str,
+        chromosomes_first: bool,
+        naming: &NamingScheme,
         n_length: usize,
-    ) {
+    ) -> Vec<TpfEntry> {
+        //
+        // Re-order the scaffolds, keeping the entries of each scaffold
+        // in their original order.
+        // size - longest scaffold first, using the TPF/AGP coordinates
+        // name - natural order of the names, SUPER_1, SUPER_2 ... SUPER_10
+        // none - order of first appearance
+        // chromosomes_first puts unlocalised then unplaced scaffolds after
+        // all of the chromosomes, each group sorted as above
+        //
+        let mut groups: Vec<(String, usize, Vec<&TpfEntry>)> = group_by_scaffold(&entries)
+            .into_iter()
+            .map(|(name, scaffold_entries)| {
+                let size = scaffold_entries.iter().map(|x| x.length(n_length)).sum();
+                (name, size, scaffold_entries)
+            })
+            .collect();
+
+        // sort_by is stable, so ties keep their original order
+        groups.sort_by(|a, b| {
+            let class = if chromosomes_first {
+                naming.role(&a.0).rank().cmp(&naming.role(&b.0).rank())
+            } else {
+                Ordering::Equal
+            };
+            class.then_with(|| match sort {
+                "size" => b.1.cmp(&a.1),
+                "name" => natural_cmp(&a.0, &b.0),
+                _ => Ordering::Equal,
+            })
+        });
+
+        groups
+            .into_iter()
+            .flat_map(|(_, _, scaffold_entries)| scaffold_entries.into_iter().cloned())
+            .collect()
+    }
+
+    pub fn scaffold_order(entries: &[TpfEntry]) -> Vec<String> {
+        // New scaffold names in the order they first appear
+        group_by_scaffold(entries)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    pub fn sequence_lengths(fasta_file: &str) -> Result<HashMap<String, usize>, Box<dyn Error>> {
+        // Lengths straight from the .fai, built if need be,
+        // a gzipped fasta has to be read through
+        if detect_compression(fasta_file)? == Compression::Gzip {
+            return validate_fasta(fasta_file);
+        }
+        Ok(index_lengths(&ensure_fai(fasta_file)?))
+    }
+
+    pub fn index_lengths(index: &fasta::fai::Index) -> HashMap<String, usize> {
+        index
+            .as_ref()
+            .iter()
+            .map(|record| {
+                (
+                    String::from_utf8_lossy(record.name()).to_string(),
+                    record.length() as usize,
+                )
+            })
+            .collect()
+    }
+
+    // A stretch of an original scaffold which is either not used
+    // by the layout, or used by more than one of its lines
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Coverage {
+        pub scaffold: String,
+        pub start: usize,
+        pub end: usize,
+        pub lines: Vec<usize>,
+    }
+
+    #[derive(Debug, Default, PartialEq, Eq)]
+    pub struct LayoutReport {
+        pub errors: Vec<String>,
+        pub uncovered: Vec<Coverage>,
+        pub duplicated: Vec<Coverage>,
+    }
+
+    impl LayoutReport {
+        pub fn is_valid(&self) -> bool {
+            self.errors.is_empty() && self.uncovered.is_empty() && self.duplicated.is_empty()
+        }
+    }
+
+    impl std::fmt::Display for LayoutReport {
+        fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+            for error in &self.errors {
+                writeln!(fmt, "ERROR: {}", error)?;
+            }
+            for region in &self.uncovered {
+                writeln!(
+                    fmt,
+                    "UNCOVERED: {}:{}-{} ({} bp) is not in the layout",
+                    region.scaffold,
+                    region.start,
+                    region.end,
+                    region.end + 1 - region.start
+                )?;
+            }
+            for region in &self.duplicated {
+                writeln!(
+                    fmt,
+                    "DUPLICATED: {}:{}-{} ({} bp) is used by lines {:?}",
+                    region.scaffold,
+                    region.start,
+                    region.end,
+                    region.end + 1 - region.start,
+                    region.lines
+                )?;
+            }
+            Ok(())
+        }
+    }
+
+    pub fn check_layout(
+        entries: &[(usize, TpfEntry)],
+        lengths: &HashMap<String, usize>,
+    ) -> LayoutReport {
         //
-        // TPF is in the input TPF order, this will continue to be the case until
-        // such time that the script starts modifying the TPF in place.
+        // Check every component of the layout against the lengths
+        // of the original scaffolds, then walk each scaffold in
+        // coordinate order to find the bases that are used never
+        // or more than once
         //
-        // This now happends but this is ok as the order of the final scaffolds
-        // isn't essential as long as the data is correct.
+        let mut report = LayoutReport::default();
+        let mut by_scaffold: HashMap<&String, Vec<(usize, usize, usize)>> = HashMap::new();
+
+        for (line_number, entry) in entries {
+            let TpfEntry::Component(tpf) = entry else {
+                continue;
+            };
+            match lengths.get(&tpf.ori_scaffold) {
+                None => report.errors.push(format!(
+                    "line {}: {} is not in the fasta",
+                    line_number, tpf.ori_scaffold
+                )),
+                Some(length) if tpf.end_coord > *length => report.errors.push(format!(
+                    "line {}: {}:{}-{} runs past the end of {} (length {})",
+                    line_number,
+                    tpf.ori_scaffold,
+                    tpf.start_coord,
+                    tpf.end_coord,
+                    tpf.ori_scaffold,
+                    length
+                )),
+                Some(_) => by_scaffold.entry(&tpf.ori_scaffold).or_default().push((
+                    tpf.start_coord,
+                    tpf.end_coord,
+                    *line_number,
+                )),
+            }
+        }
+
+        let mut scaffolds: Vec<(&String, &usize)> = lengths.iter().collect();
+        scaffolds.sort_by(|a, b| natural_cmp(a.0, b.0));
+
+        for (scaffold, length) in scaffolds {
+            let mut ranges = by_scaffold.remove(scaffold).unwrap_or_default();
+            ranges.sort();
+
+            // covered_to is the last base used so far, with the lines
+            // that reach it, so an overlap can name both sides
+            let mut covered_to: usize = 0;
+            let mut covering: Vec<(usize, usize)> = Vec::new();
+            for (start, end, line_number) in ranges {
+                if start > covered_to + 1 {
+                    report.uncovered.push(Coverage {
+                        scaffold: scaffold.to_owned(),
+                        start: covered_to + 1,
+                        end: start - 1,
+                        lines: Vec::new(),
+                    });
+                }
+                if start <= covered_to {
+                    let mut lines: Vec<usize> = covering
+                        .iter()
+                        .filter(|x| x.0 >= start)
+                        .map(|x| x.1)
+                        .collect();
+                    lines.push(line_number);
+                    report.duplicated.push(Coverage {
+                        scaffold: scaffold.to_owned(),
+                        start,
+                        end: end.min(covered_to),
+                        lines,
+                    });
+                }
+                covering.retain(|x| x.0 >= start);
+                covering.push((end, line_number));
+                covered_to = covered_to.max(end);
+            }
+            if covered_to < *length {
+                report.uncovered.push(Coverage {
+                    scaffold: scaffold.to_owned(),
+                    start: covered_to + 1,
+                    end: *length,
+                    lines: Vec::new(),
+                });
+            }
+        }
+        report
+    }
+
+    pub fn read_layout(
+        tpf_file: &String,
+        format: &str,
+    ) -> Result<Vec<(usize, TpfEntry)>, Box<dyn Error>> {
+        // Read the layout as the format says, or work it out
+        let use_agp = match format {
+            "agp" => true,
+            "tpf" => false,
+            _ => is_agp(tpf_file),
+        };
+        if use_agp {
+            println!("READING LAYOUT AS AGP: {}", tpf_file);
+            read_agp(tpf_file)
+        } else {
+            read_tpf(tpf_file)
+        }
+    }
+
+    pub fn validate_tpf(arguments: std::option::Option<&ArgMatches>) {
         //
-        // In the future an optional sort function should be added
+        // Check a TPF (or AGP) against the fasta it was made from
+        // without writing anything
         //
-        let _data_file = File::create(output);
-        let mut file = OpenOptions::new()
-            .write(true)
-            .open(output)
-            .expect("creation failed");
-
-        let _debugger = File::create("debug.txt");
-        let mut file2 = OpenOptions::new()
-            .write(true)
-            .open("debug.txt")
-            .expect("creation failed");
-
-        let uniques = get_uniques(&tpf_data);
-
-        // This is inefficient as we are scanning through the fasta_data, uniques
-        // ( equal to number of scaffolds) number of times
-        // If uniques is 10 long and fasta is 100, then this is 1000 scans through in total.
-        // we need to change x to something more descriptive
-        for x in uniques {
-            println!("NOW WRITING DATA FOR: {:?}", &x);
-            // X = "SUPER_1"
-            let stringy = format!(">{x}\n");
-            file.write_all(stringy.as_bytes())
-                .expect("Unable to write to file");
-
-            // file2 will collect what went where
-            // no sequence data
-            file2
-                .write_all(stringy.as_bytes())
-                .expect("Unable to write to file");
-
-            let mut data: MyRecord = MyRecord {
-                // would it be better to use x.clone()
-                name: "".to_string(),
-                sequence: Vec::new(),
+        let fasta_file: &String = arguments.unwrap().get_one::<String>("fasta").unwrap();
+        let tpf_file: &String = arguments.unwrap().get_one::<String>("tpf").unwrap();
+        let format: &String = arguments.unwrap().get_one::<String>("format").unwrap();
+
+        println!("Validating: {} against {}", tpf_file, fasta_file);
+
+        let entries = match read_layout(tpf_file, format) {
+            Ok(entries) => entries,
+            Err(e) => {
+                println!("{} {}", "ERROR:".red(), e);
+                std::process::exit(1)
+            }
+        };
+        let lengths = match sequence_lengths(fasta_file) {
+            Ok(lengths) => lengths,
+            Err(e) => panic!("Something is wrong with the file! | {}", e),
+        };
+
+        let report = check_layout(&entries, &lengths);
+        if report.is_valid() {
+            println!(
+                "{}",
+                format!(
+                    "LAYOUT IS VALID: {} components cover all {} scaffolds",
+                    components(&entries.into_iter().map(|x| x.1).collect::<Vec<_>>()).len(),
+                    lengths.len()
+                )
+                .green()
+            );
+        } else {
+            print!("{}", report);
+            println!(
+                "{}",
+                format!(
+                    "LAYOUT IS NOT VALID: {} errors, {} uncovered regions, {} duplicated regions",
+                    report.errors.len(),
+                    report.uncovered.len(),
+                    report.duplicated.len()
+                )
+                .red()
+            );
+            std::process::exit(1)
+        }
+    }
+
+    // Somewhere to get the sequence of a component from, already
+    // in the orientation given in the TPF/AGP
+    pub trait ComponentSource {
+        fn fetch(&mut self, tpf: &Tpf) -> Result<Vec<u8>, Box<dyn Error>>;
+    }
+
+    // Components read on demand from an indexed fasta, only the
+    // requested slice of the original scaffold is read into memory
+    pub struct IndexedSource<'a, R> {
+        reader: fasta::io::IndexedReader<R>,
+        // Scaffold lengths, as from sequence_lengths
+        lengths: &'a HashMap<String, usize>,
+    }
+
+    impl<'a, R> IndexedSource<'a, R> {
+        pub fn new(
+            reader: fasta::io::IndexedReader<R>,
+            lengths: &'a HashMap<String, usize>,
+        ) -> IndexedSource<'a, R> {
+            IndexedSource { reader, lengths }
+        }
+    }
+
+    impl<R: BufRead + Seek> ComponentSource for IndexedSource<'_, R> {
+        fn fetch(&mut self, tpf: &Tpf) -> Result<Vec<u8>, Box<dyn Error>> {
+            // The query would stop quietly at the end of the scaffold
+            let length = match self.lengths.get(&tpf.ori_scaffold) {
+                Some(length) => *length,
+                None => return Err(format!("{} is not in the fasta", tpf.ori_scaffold).into()),
             };
+            if tpf.end_coord > length {
+                return Err(format!(
+                    "{}:{}-{} runs past the end of {} (length {})",
+                    tpf.ori_scaffold, tpf.start_coord, tpf.end_coord, tpf.ori_scaffold, length
+                )
+                .into());
+            }
+            let region = Region::new(
+                tpf.ori_scaffold.as_str(),
+                Position::try_from(tpf.start_coord)?..=Position::try_from(tpf.end_coord)?,
+            );
+            let record = self.reader.query(&region)?;
+            orient(record.sequence().as_ref(), &tpf.orientation).map_err(|e| {
+                format!(
+                    "{}:{}-{} | {}",
+                    tpf.ori_scaffold, tpf.start_coord, tpf.end_coord, e
+                )
+                .into()
+            })
+        }
+    }
 
-            x.clone_into(&mut data.name);
-            for tpf in &tpf_data {
-                // x should be data.name and we should probably transfer ownership?
-                if tpf.new_scaffold == x {
-                    for fasta in &fasta_data {
-                        if fasta.tpf == *tpf {
-                            let stringy = format!("\t{}\n", tpf);
-                            file2
-                                .write_all(stringy.as_bytes())
-                                .expect("Unable to write to file");
-                            data.sequence.push(fasta.sequence.to_owned());
+    // Writes fasta records wrapped at line_len without
+    // holding more than the current piece in memory
+    struct WrappedWriter<'a> {
+        inner: &'a mut dyn Write,
+        line_len: usize,
+        column: usize,
+    }
+
+    impl WrappedWriter<'_> {
+        fn header(&mut self, name: &str) -> std::io::Result<()> {
+            self.finish()?;
+            writeln!(self.inner, ">{}", name)
+        }
+
+        fn sequence(&mut self, mut data: &[u8]) -> std::io::Result<()> {
+            while !data.is_empty() {
+                let take = (self.line_len - self.column).min(data.len());
+                self.inner.write_all(&data[..take])?;
+                self.column += take;
+                data = &data[take..];
+                if self.column == self.line_len {
+                    self.inner.write_all(b"\n")?;
+                    self.column = 0;
+                }
+            }
+            Ok(())
+        }
+
+        fn gap(&mut self, mut length: usize) -> std::io::Result<()> {
+            let n_string = [b'N'; 1024];
+            while length > 0 {
+                let take = length.min(n_string.len());
+                self.sequence(&n_string[..take])?;
+                length -= take;
+            }
+            Ok(())
+        }
+
+        fn finish(&mut self) -> std::io::Result<()> {
+            if self.column > 0 {
+                self.inner.write_all(b"\n")?;
+                self.column = 0;
+            }
+            Ok(())
+        }
+    }
+
+    fn agp_component_line(
+        scaffold: &str,
+        object_start: usize,
+        part_number: usize,
+        tpf: &Tpf,
+        length: usize,
+    ) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            scaffold,
+            object_start,
+            object_start + length - 1,
+            part_number,
+            tpf.component_type,
+            tpf.ori_scaffold,
+            tpf.start_coord,
+            tpf.start_coord + length - 1,
+            if tpf.orientation == "MINUS" { "-" } else { "+" }
+        )
+    }
+
+    fn agp_gap_line(
+        scaffold: &str,
+        object_start: usize,
+        part_number: usize,
+        gap: &Gap,
+        length: usize,
+    ) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            scaffold,
+            object_start,
+            object_start + length - 1,
+            part_number,
+            gap.component_type,
+            length,
+            gap.gap_type,
+            if gap.linkage_evidence == "na" {
+                "no"
+            } else {
+                "yes"
+            },
+            gap.linkage_evidence
+        )
+    }
+
+    pub fn write_curated<S: ComponentSource>(
+        source: &mut S,
+        entries: &[TpfEntry],
+        fasta_out: &mut dyn Write,
+        mut debug_out: Option<&mut dyn Write>,
+        mut agp_out: Option<&mut dyn Write>,
+        n_length: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        //
+        // Write each new scaffold in turn, fetching each component as it
+        // is needed and writing it straight out, so only one component is
+        // held at a time.
+        // The debug trace and AGP are written in the same pass, using the
+        // length of the sequence actually written for each component, so
+        // they always agree with the fasta.
+        //
+        // The debug trace is tab separated, one line per component:
+        // new_scaffold new_start new_end part original_scaffold
+        // original_start original_end orientation
+        // new_* are 1-based coordinates in the curated fasta, part is the
+        // AGP part number (gaps are parts too but are not listed)
+        //
+        let mut fasta = WrappedWriter {
+            inner: fasta_out,
+            line_len: 60,
+            column: 0,
+        };
+
+        if let Some(debug) = debug_out.as_mut() {
+            writeln!(
+                debug,
+                "#new_scaffold\tnew_start\tnew_end\tpart\toriginal_scaffold\toriginal_start\toriginal_end\torientation"
+            )?;
+        }
+        if let Some(agp) = agp_out.as_mut() {
+            writeln!(agp, "##agp-version\t2.1")?;
+            writeln!(agp, "# AGP created by FasMan curate")?;
+        }
+
+        for (scaffold, scaffold_entries) in group_by_scaffold(entries) {
+            println!("NOW WRITING DATA FOR: {:?}", &scaffold);
+            fasta.header(&scaffold)?;
+
+            let mut object_end: usize = 0;
+            let mut part_number: usize = 0;
+            for entry in scaffold_entries {
+                match entry {
+                    TpfEntry::Component(tpf) => {
+                        let sequence = source.fetch(tpf)?;
+                        fasta.sequence(&sequence)?;
+
+                        part_number += 1;
+                        // debug_out collects what went where, no sequence data
+                        if let Some(debug) = debug_out.as_mut() {
+                            writeln!(
+                                debug,
+                                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                                scaffold,
+                                object_end + 1,
+                                object_end + sequence.len(),
+                                part_number,
+                                tpf.ori_scaffold,
+                                tpf.start_coord,
+                                tpf.end_coord,
+                                tpf.orientation
+                            )?;
+                        }
+                        if let Some(agp) = agp_out.as_mut() {
+                            writeln!(
+                                agp,
+                                "{}",
+                                agp_component_line(
+                                    &scaffold,
+                                    object_end + 1,
+                                    part_number,
+                                    tpf,
+                                    sequence.len()
+                                )
+                            )?;
                         }
+                        object_end += sequence.len();
+                    }
+                    TpfEntry::Gap(gap) => {
+                        let gap_length = gap.resolved_length(n_length);
+                        fasta.gap(gap_length)?;
+
+                        part_number += 1;
+                        if let Some(agp) = agp_out.as_mut() {
+                            writeln!(
+                                agp,
+                                "{}",
+                                agp_gap_line(
+                                    &scaffold,
+                                    object_end + 1,
+                                    part_number,
+                                    gap,
+                                    gap_length
+                                )
+                            )?;
+                        }
+                        object_end += gap_length;
                     }
                 }
             }
+        }
+        fasta.finish()?;
+        Ok(())
+    }
 
-            // Should be it's own function really
-            // This actually writes the new fasta file
-            // Joining the data together with user (default = 200)
-            // N's (gap)
-
-            let line_len: usize = 60;
-            let fixed = data.sequence;
-            let n_string = "N".repeat(n_length);
-            let fixed2 = fixed.join(&n_string); //.join required a borrowed str
-            let fixed3 = fixed2
-                .as_bytes()
-                .chunks(line_len)
-                .map(str::from_utf8)
-                .collect::<Result<Vec<&str>, _>>()
-                .unwrap();
-
-            for i in fixed3 {
-                let formatted = i.to_owned() + "\n";
-                file.write_all(formatted.as_bytes()).unwrap();
-            }
+    fn next_to(output: &str, extension: &str) -> String {
+        // new.fasta or new.fasta.gz -> new.{extension}
+        let uncompressed = output
+            .strip_suffix(".gz")
+            .or_else(|| output.strip_suffix(".bgz"))
+            .unwrap_or(output);
+        Path::new(uncompressed)
+            .with_extension(extension)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    pub fn debug_path(output: &str) -> String {
+        // The debug trace sits next to the output, new.fasta -> new.debug.tsv
+        next_to(output, "debug.tsv")
+    }
+
+    pub fn naming_scheme(arguments: &ArgMatches) -> NamingScheme {
+        // From the -p, -r, -u, -U and -m shared by curate and liftover
+        NamingScheme {
+            prefix: arguments.get_one::<String>("prefix").unwrap().to_owned(),
+            tpf_prefix: arguments
+                .get_one::<String>("tpf_prefix")
+                .unwrap()
+                .to_owned(),
+            unloc_suffix: arguments
+                .get_one::<String>("unloc_suffix")
+                .unwrap()
+                .to_owned(),
+            unplaced_suffix: arguments
+                .get_one::<String>("unplaced_suffix")
+                .map(|x| x.to_owned()),
+            assignments: match arguments.get_one::<String>("chromosome_map") {
+                Some(table) => match read_assignments(table) {
+                    Ok(assignments) => assignments,
+                    Err(e) => panic!("Something is wrong with the chromosome map! | {}", e),
+                },
+                None => HashMap::new(),
+            },
         }
     }
 
-    #[allow(clippy::needless_borrow)]
-    #[allow(clippy::let_and_return)]
     pub fn curate_fasta(arguments: std::option::Option<&ArgMatches>) {
         //
         // Generate a curated fasta file based on the input TPF file
@@ -257,63 +1129,156 @@ pub mod tpf_fasta_mod {
         let tpf_file: &String = arguments.unwrap().get_one::<String>("tpf").unwrap();
         let n_length: &usize = arguments.unwrap().get_one::<usize>("n_length").unwrap();
         let output: &String = arguments.unwrap().get_one::<String>("output").unwrap();
+        let format: &String = arguments.unwrap().get_one::<String>("format").unwrap();
+        let sort: &String = arguments.unwrap().get_one::<String>("sort").unwrap();
+        let chromosomes_first: &bool = arguments
+            .unwrap()
+            .get_one::<bool>("chromosomes_first")
+            .unwrap();
+        // Components are cut out through the index, which plain gzip can't have
+        if let Ok(Compression::Gzip) = detect_compression(fasta_file) {
+            eprintln!(
+                "{}",
+                format!(
+                    "{} IS GZIP COMPRESSED, recompress it with bgzip (zcat {} | bgzip > new.fa.gz)",
+                    fasta_file, fasta_file
+                )
+                .red()
+            );
+            std::process::exit(1);
+        }
+        let naming = naming_scheme(arguments.unwrap());
+        // The AGP sits next to the fasta unless told otherwise
+        let agp_output: String = match arguments.unwrap().get_one::<String>("agp") {
+            Some(agp) => agp.to_owned(),
+            None => next_to(output, "agp"),
+        };
         println!("LET'S GET CURATING THAT FASTA!");
 
-        // Stacker is supposed to increase the stack size
-        // once memory runs out
-        stacker::maybe_grow(32 * 1024, 1024 * 5120, || {
-            match validate_fasta(fasta_file) {
-                // validate returns Vec of headers - basically indexes it
-                Ok(fasta_d) => {
-                    let tpf_data = parse_tpf(&tpf_file);
-
-                    //
-                    // Start indexed reader of the input fasta
-                    // if valid then use the data
-                    //
-                    let reader =
-                        fasta::indexed_reader::Builder::default().build_from_path(fasta_file);
-                    let fasta_repo = match reader {
-                        Ok(data) => {
-                            let adapter = IndexedReader::new(data);
-
-                            // Now read the fasta and return as a queryable object
-                            let repository = fasta::Repository::new(adapter);
-                            repository
-                        }
-                        Err(e) => panic!("NOODLES/STD::IO ERROR: {:?}\n Likely a malformatted FAI - Check that the seperators are TABS not spaces!!!", e),
-                    };
-
-                    //
-                    // For unique scaffold in the fasta file iter through and
-                    // parse sequence for each line in the tpf
-                    // The tpf will contain multiple enteries for each scaffold, minimum of one entry.
-                    //
-                    let mut new_fasta_data: Vec<NewFasta> = Vec::new();
-                    for i in fasta_d {
-                        // for header in fasta_d
-                        // subset the tpf on header and length
-                        // cross referencing with fasta_d
-                        let subset_tpf = subset_vec_tpf(&tpf_data, (&i.0, &i.1));
-
-                        // Query the fasta for scaffold = header
-                        let sequence = fasta_repo.get(&i.0.as_bytes()).transpose();
-
-                        // if exists then get the seqeuence, return a tpf object
-                        // containing the trimmed sequence
-                        match sequence {
-                            Ok(data) => {
-                                let subset_results = parse_seq(data, subset_tpf);
-                                new_fasta_data.extend(subset_results);
-                            }
-                            Err(e) => panic!("{:?}", e),
-                        };
-                    }
-                    // Write it all out to fasta
-                    save_to_fasta(new_fasta_data, tpf_data, output, n_length.to_owned())
+        let use_agp = match format.as_str() {
+            "agp" => true,
+            "tpf" => false,
+            _ => is_agp(tpf_file),
+        };
+        let numbered = match read_layout(tpf_file, if use_agp { "agp" } else { "tpf" }) {
+            Ok(entries) => entries,
+            Err(e) => panic!("Something is wrong with the layout! | {}", e),
+        };
+
+        // Anything which can't be cut from the fasta stops us here,
+        // unused or reused sequence is worth a warning but may be wanted
+        // The index is built (or checked) once, for the lengths and the reader
+        let index = match ensure_fai(fasta_file) {
+            Ok(index) => index,
+            Err(e) => panic!("Something is wrong with the file! | {}", e),
+        };
+        let lengths = index_lengths(&index);
+        let report = check_layout(&numbered, &lengths);
+        if !report.errors.is_empty() {
+            panic!("The layout does not match the fasta!\n{}", report)
+        } else if !report.is_valid() {
+            print!("{}", format!("WARNING:\n{}", report).yellow());
+        }
+
+        // AGP and TPF files carry their own gaps, a TPF without
+        // any GAP lines has its components joined with n_length gaps
+        let entries: Vec<TpfEntry> = numbered.into_iter().map(|x| x.1).collect();
+        let entries = if use_agp || entries.iter().any(|x| matches!(x, TpfEntry::Gap(_))) {
+            entries
+        } else {
+            gapped_tpf(components(&entries), n_length.to_owned())
+        };
+        let entries = match apply_naming(entries, &naming) {
+            Ok(entries) => entries,
+            Err(e) => panic!("Something is wrong with the naming! | {}", e),
+        };
+        let entries = sort_entries(
+            entries,
+            sort,
+            *chromosomes_first,
+            &naming,
+            n_length.to_owned(),
+        );
+
+        //
+        // Start indexed reader of the input fasta, components
+        // are read from it as they are written out
+        //
+        let reader = open_fasta_with_index(fasta_file, index);
+        let mut source = match reader {
+            Ok(data) => IndexedSource::new(data, &lengths),
+            Err(e) => panic!("NOODLES/STD::IO ERROR: {:?}\n Likely a missing or malformatted FAI (or GZI if bgzipped) - Check that the seperators are TABS not spaces!!!", e),
+        };
+
+        let compression =
+            output_compression(output, arguments.unwrap().get_one::<String>("compress"));
+        let mut fasta_out = FastaOutput::create(output, compression).expect("creation failed");
+        // The debug trace sits next to the output unless given a path or turned off
+        let debug_file: Option<String> = if *arguments.unwrap().get_one::<bool>("no_debug").unwrap()
+        {
+            None
+        } else {
+            Some(match arguments.unwrap().get_one::<String>("debug") {
+                Some(debug) => debug.to_owned(),
+                None => debug_path(output),
+            })
+        };
+        let mut debug_out = debug_file
+            .as_ref()
+            .map(|x| BufWriter::new(File::create(x).expect("creation failed")));
+        let mut agp_out = BufWriter::new(File::create(&agp_output).expect("creation failed"));
+
+        match write_curated(
+            &mut source,
+            &entries,
+            &mut fasta_out,
+            debug_out.as_mut().map(|x| x as &mut dyn Write),
+            Some(&mut agp_out),
+            n_length.to_owned(),
+        ) {
+            Ok(_) => {
+                fasta_out.finish().expect("Unable to write to file");
+                agp_out.flush().expect("Unable to write to file");
+                if let Some(debug) = debug_out.as_mut() {
+                    debug.flush().expect("Unable to write to file");
+                }
+                println!("AGP WRITTEN TO: {}", agp_output);
+                if let Some(debug) = &debug_file {
+                    println!("DEBUG TRACE WRITTEN TO: {}", debug);
                 }
-                Err(e) => panic!("Something is wrong with the file! | {}", e),
             }
-        })
+            Err(e) => panic!("Something is wrong with the file! | {}", e),
+        }
+
+        // ENA chromosome and unlocalised lists for submission
+        let chromosome_list = next_to(output, "chromosome_list.tsv");
+        let unlocalised_list = next_to(output, "unlocalised_list.tsv");
+        let mut chromosome_out =
+            BufWriter::new(File::create(&chromosome_list).expect("creation failed"));
+        let mut unlocalised_out =
+            BufWriter::new(File::create(&unlocalised_list).expect("creation failed"));
+        naming
+            .write_chromosome_lists(
+                &scaffold_order(&entries),
+                &mut chromosome_out,
+                &mut unlocalised_out,
+            )
+            .and_then(|_| chromosome_out.flush())
+            .and_then(|_| unlocalised_out.flush())
+            .expect("Unable to write to file");
+        println!(
+            "CHROMOSOME LISTS WRITTEN TO: {} {}",
+            chromosome_list, unlocalised_list
+        );
+        // What the curation did, compared to the input assembly
+        let report_file = next_to(output, "curation_report.tsv");
+        let report = curation_report(&entries, &lengths, n_length.to_owned());
+        let mut report_out = BufWriter::new(File::create(&report_file).expect("creation failed"));
+        report
+            .write_tsv(&mut report_out)
+            .and_then(|_| report_out.flush())
+            .expect("Unable to write to file");
+        print!("{}", report);
+        println!("CURATION REPORT WRITTEN TO: {}", report_file);
     }
 }
@@ -2,7 +2,7 @@ pub mod yaml_validator_mod {
     use clap::ArgMatches;
     use colored::Colorize;
     use csv::ReaderBuilder;
-    use noodles::{cram, fasta};
+    use noodles::cram;
     use serde::{Deserialize, Serialize};
     use std::fs::{self, File};
     use std::marker::PhantomData;
@@ -104,7 +104,7 @@ pub mod yaml_validator_mod {
             failures
         }
 
-        fn check_secondaries(&'a self, secondary_list: Vec<&'a Vec<String>>) -> Vec<&String> {
+        fn check_secondaries(&'a self, secondary_list: Vec<&'a Vec<String>>) -> Vec<&'a String> {
             let mut failures: Vec<&String> = Vec::new();
             for i in secondary_list {
                 let collection = i
@@ -226,7 +226,7 @@ pub mod yaml_validator_mod {
         #[allow(dead_code)]
         /// Validate that the input fasta is infact a fasta format and count records.
         fn validate_fasta(&self) -> String {
-            let reader = fasta::reader::Builder.build_from_path(&self.reference_file);
+            let reader = crate::generics::open_fasta(&self.reference_file);
 
             let mut binding = reader.expect("NO VALID HEADER / SEQUENCE PAIRS");
             let result = binding.records();
@@ -243,7 +243,6 @@ pub mod yaml_validator_mod {
 
             match file {
                 Ok(valid_data) => {
-                    format!("PASS: {}", csv_path);
                     let name = &csv_path.split('/').collect::<Vec<&str>>();
 
                     let mut reader = ReaderBuilder::new()
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use fasta_manipulation::curation_report_mod::{curation_report, Change, ChangeKind};
+use fasta_manipulation::tpf_fasta_mod::{parse_tpf_entries, sequence_lengths, Tpf, TpfEntry};
+
+fn component(
+    ori_scaffold: &str,
+    start: usize,
+    end: usize,
+    new: &str,
+    orientation: &str,
+) -> TpfEntry {
+    TpfEntry::Component(Tpf {
+        ori_scaffold: ori_scaffold.to_string(),
+        start_coord: start,
+        end_coord: end,
+        new_scaffold: new.to_string(),
+        orientation: orientation.to_string(),
+        component_type: 'W',
+    })
+}
+
+#[test]
+fn check_curation_report_small_test() {
+    let entries =
+        parse_tpf_entries(&"test_data/iyAndFlav1/small/small_test.curated.tpf".to_string());
+    let lengths = sequence_lengths("test_data/iyAndFlav1/small/small_test.fa").unwrap();
+    let report = curation_report(&entries, &lengths, 100);
+
+    assert_eq!(report.count(ChangeKind::Break), 2);
+    assert_eq!(report.count(ChangeKind::Join), 1);
+    // SCAFFOLD_1 is only used MINUS, so isn't inverted
+    assert_eq!(report.count(ChangeKind::Inversion), 0);
+    assert_eq!(report.count(ChangeKind::Moved), 2);
+    assert_eq!(report.count(ChangeKind::Dropped), 3);
+    assert_eq!(report.count(ChangeKind::Duplicated), 0);
+    assert!(report.changes.contains(&Change {
+        kind: ChangeKind::Moved,
+        original: "SCAFFOLD_2:12-20".to_string(),
+        curated: "SUPER_3_unloc_1".to_string(),
+        detail: "from SUPER_1".to_string(),
+        size: 9,
+    }));
+
+    assert_eq!(report.before.scaffolds, 3);
+    assert_eq!(report.before.total_length, 74 + 74 + 85);
+    assert_eq!(report.after.scaffolds, 4);
+    // SUPER_1 is 9 + 200 + 11
+    assert_eq!(report.after.n50, 220);
+
+    let mut tsv: Vec<u8> = Vec::new();
+    report.write_tsv(&mut tsv).unwrap();
+    let tsv = String::from_utf8(tsv).unwrap();
+    assert!(tsv.starts_with("#type\toriginal\tcurated\tdetail\n"));
+    assert!(tsv.contains("DROPPED\tSCAFFOLD_1:10-74\t-\t65 bp\n"));
+    assert!(tsv.ends_with("N50\t74\t220\t-\n"));
+}
+
+#[test]
+fn check_curation_report_continued_pieces() {
+    // Pieces that carry on from each other, in either orientation, are
+    // neither a break nor a join
+    let entries = vec![
+        component("SCAFFOLD_1", 11, 20, "SUPER_1", "MINUS"),
+        component("SCAFFOLD_1", 1, 10, "SUPER_1", "MINUS"),
+        component("SCAFFOLD_2", 1, 5, "SUPER_2", "PLUS"),
+        component("SCAFFOLD_2", 6, 10, "SUPER_2", "PLUS"),
+        component("SCAFFOLD_2", 11, 20, "SUPER_2", "PLUS"),
+    ];
+    let lengths = HashMap::from([
+        ("SCAFFOLD_1".to_string(), 20),
+        ("SCAFFOLD_2".to_string(), 20),
+    ]);
+    let report = curation_report(&entries, &lengths, 100);
+
+    assert_eq!(report.count(ChangeKind::Break), 0);
+    assert_eq!(report.count(ChangeKind::Join), 0);
+    assert_eq!(report.count(ChangeKind::Inversion), 0);
+    assert_eq!(report.count(ChangeKind::Dropped), 0);
+
+    // Swapping two pieces breaks the scaffold twice
+    let swapped = vec![
+        component("SCAFFOLD_2", 6, 10, "SUPER_2", "PLUS"),
+        component("SCAFFOLD_2", 1, 5, "SUPER_2", "PLUS"),
+        component("SCAFFOLD_2", 11, 20, "SUPER_2", "PLUS"),
+    ];
+    let report = curation_report(&swapped, &lengths, 100);
+    assert_eq!(report.count(ChangeKind::Break), 2);
+    assert_eq!(report.count(ChangeKind::Join), 2);
+    assert_eq!(report.count(ChangeKind::Dropped), 1);
+    assert!(report.to_string().contains("Dropped:\t1 regions (20 bp)"));
+
+    // Only the piece flipped against the rest of SCAFFOLD_2 is inverted
+    let inverted = vec![
+        component("SCAFFOLD_2", 1, 5, "SUPER_2", "PLUS"),
+        component("SCAFFOLD_2", 6, 10, "SUPER_2", "MINUS"),
+        component("SCAFFOLD_2", 11, 20, "SUPER_2", "PLUS"),
+    ];
+    let report = curation_report(&inverted, &lengths, 100);
+    assert_eq!(
+        report
+            .changes
+            .iter()
+            .filter(|x| x.kind == ChangeKind::Inversion)
+            .collect::<Vec<&Change>>(),
+        vec![&Change {
+            kind: ChangeKind::Inversion,
+            original: "SCAFFOLD_2:6-10".to_string(),
+            curated: "SUPER_2".to_string(),
+            detail: "MINUS in PLUS".to_string(),
+            size: 5,
+        }]
+    );
+}
@@ -0,0 +1,204 @@
+use assert_cmd::Command;
+use std::fs;
+use std::io::BufReader;
+use tempfile::Builder;
+
+use fasta_manipulation::extract_mod::{
+    extract_regions, parse_extraction, read_bed_regions, Extraction,
+};
+use fasta_manipulation::faidx_mod::parse_region;
+
+const FASTA: &str = ">SCAFFOLD_1\nATGCATGC\nCGTATAGA\n>SCAFFOLD_3\nAGTGTatt\nTTTATGCA\nGR\n";
+
+#[test]
+fn check_parse_extraction_and_naming() {
+    let index = noodles::fasta::fai::Index::from(vec![noodles::fasta::fai::Record::new(
+        "SCAFFOLD_1",
+        3000,
+        12,
+        60,
+        61,
+    )]);
+    let extraction = parse_extraction("SCAFFOLD_1:1001-2000", &index).unwrap();
+    assert_eq!(
+        extraction,
+        Extraction {
+            scaffold: "SCAFFOLD_1".to_string(),
+            start: Some(1001),
+            end: Some(2000),
+            name: None,
+            reverse: false,
+        }
+    );
+    assert_eq!(extraction.region(), "SCAFFOLD_1:1001-2000");
+    assert_eq!(
+        extraction.header("{scaffold}_{start}_{end}({strand}) {name}"),
+        "SCAFFOLD_1_1001_2000(+) SCAFFOLD_1:1001-2000"
+    );
+
+    // Exactly what faidx accepts
+    for region in [
+        "SCAFFOLD_1",
+        "SCAFFOLD_1:5",
+        "SCAFFOLD_1:10-x",
+        "SCAFFOLD_1:1-3001",
+        "SCAFFOLD_2",
+    ] {
+        assert_eq!(
+            parse_extraction(region, &index).map(|x| x.region()),
+            parse_region(region, &index).map(|x| x.to_string())
+        );
+    }
+    assert_eq!(parse_extraction("SCAFFOLD_1", &index).unwrap().start, None);
+}
+
+#[test]
+fn check_read_bed_regions() {
+    let bed = "track name=genes\n\
+               SCAFFOLD_1\t0\t4\tgeneA\t0\t+\n\
+               SCAFFOLD_3\t10\t18\tgeneB\t0\t-\n\
+               SCAFFOLD_3\t2\t5\n";
+    let regions = read_bed_regions(&mut BufReader::new(bed.as_bytes()), true).unwrap();
+    assert_eq!(regions.len(), 3);
+    assert_eq!(regions[0].region(), "SCAFFOLD_1:1-4");
+    assert_eq!(regions[0].name, Some("geneA".to_string()));
+    assert!(!regions[0].reverse);
+    assert!(regions[1].reverse);
+    assert_eq!(regions[2].header("{name}"), "SCAFFOLD_3:3-5");
+
+    // Strand ignored unless asked for
+    let regions = read_bed_regions(&mut BufReader::new(bed.as_bytes()), false).unwrap();
+    assert!(!regions[1].reverse);
+
+    let error = read_bed_regions(
+        &mut BufReader::new("SCAFFOLD_1\t0\t4\nSCAFFOLD_1\t9\t4\n".as_bytes()),
+        false,
+    )
+    .unwrap_err();
+    assert!(error.to_string().starts_with("line 2:"));
+}
+
+#[test]
+fn check_extract_regions_against_the_index() {
+    let fasta = ">SCAFFOLD_1\nATGCATGC\nCGTA\n";
+    let index = noodles::fasta::fai::Index::from(vec![noodles::fasta::fai::Record::new(
+        "SCAFFOLD_1",
+        12,
+        12,
+        8,
+        9,
+    )]);
+    let mut reader = noodles::fasta::io::IndexedReader::new(
+        std::io::Cursor::new(fasta.as_bytes()),
+        index.clone(),
+    );
+    let bed = "SCAFFOLD_1\t6\t10\tgeneA\t0\t-\n";
+    let regions = read_bed_regions(&mut BufReader::new(bed.as_bytes()), true).unwrap();
+    let mut out: Vec<u8> = Vec::new();
+    extract_regions(&mut reader, &index, &regions, "{name}", 60, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), ">geneA\nCGGC\n");
+
+    for (bed, error) in [
+        (
+            "SCAFFOLD_1\t8\t13\n",
+            "SCAFFOLD_1:9-13 is outside of SCAFFOLD_1 (1-12)",
+        ),
+        ("SCAFFOLD_2\t0\t4\n", "SCAFFOLD_2 is not in the fasta"),
+    ] {
+        let regions = read_bed_regions(&mut BufReader::new(bed.as_bytes()), false).unwrap();
+        let error_found =
+            extract_regions(&mut reader, &index, &regions, "{name}", 60, &mut Vec::new())
+                .unwrap_err()
+                .to_string();
+        assert_eq!(error_found, error);
+    }
+}
+
+#[test]
+fn check_extract_command() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa");
+    let bed_path = dir.path().join("regions.bed");
+    let output = dir.path().join("extracted.fa");
+    fs::write(&fasta_path, FASTA).unwrap();
+    fs::write(
+        &bed_path,
+        "SCAFFOLD_1\t0\t4\tgeneA\t0\t+\nSCAFFOLD_3\t4\t9\tgeneB\t0\t-\n",
+    )
+    .unwrap();
+
+    // No .fai, it is built on the way
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("extract")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("SCAFFOLD_3:16-18")
+        .arg("-b")
+        .arg(&bed_path)
+        .arg("-s")
+        .arg("true")
+        .arg("-n")
+        .arg("{name}|{strand}")
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+
+    // Soft-masking and IUPAC codes survive the reverse complement
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        ">SCAFFOLD_3:16-18|+\nAGR\n>geneA|+\nATGC\n>geneB|-\nAaatA\n"
+    );
+    assert!(dir.path().join("input.fa.fai").exists());
+
+    // -i flips everything, so the - strand comes back forward
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("extract")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("SCAFFOLD_3:16-18")
+        .arg("-i")
+        .arg("true")
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        ">SCAFFOLD_3:16-18\nYCT\n"
+    );
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("extract")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("SCAFFOLD_9:1-5")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn check_extract_to_stdout_is_only_fasta() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa");
+    fs::write(&fasta_path, FASTA).unwrap();
+
+    // Piped straight into samtools or seqkit, the banner goes to stderr
+    let assert = Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("extract")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("SCAFFOLD_1:1-5")
+        .assert()
+        .success();
+    let output = assert.get_output();
+    assert_eq!(
+        String::from_utf8(output.stdout.clone()).unwrap(),
+        ">SCAFFOLD_1:1-5\nATGCA\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("WELCOME TO Fasta Manipulator"));
+}
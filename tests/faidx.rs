@@ -0,0 +1,188 @@
+use assert_cmd::Command;
+use noodles::bgzf;
+use std::fs;
+use std::io::Write;
+use tempfile::Builder;
+
+use fasta_manipulation::faidx_mod::parse_region;
+use fasta_manipulation::{bgzf_blocks, ensure_fai, open_indexed_fasta};
+
+const FASTA: &str = ">SCAFFOLD_1 first\nATGCATGC\nCGTATAGA\n>SCAFFOLD_3\nAGTGTATT\nTTTATGCA\nGG\n";
+const FAI: &str = "SCAFFOLD_1\t16\t18\t8\t9\nSCAFFOLD_3\t18\t48\t8\t9\n";
+
+#[test]
+fn check_missing_and_stale_fai_are_rebuilt() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa");
+    let fai_path = dir.path().join("input.fa.fai");
+    fs::write(&fasta_path, FASTA).unwrap();
+
+    // Missing
+    let index = ensure_fai(&fasta_path).unwrap();
+    assert_eq!(index.as_ref().len(), 2);
+    assert_eq!(fs::read_to_string(&fai_path).unwrap(), FAI);
+
+    // An index of another version of the fasta, the lengths still match
+    // but SCAFFOLD_3 has moved
+    fs::write(
+        &fai_path,
+        "SCAFFOLD_1\t16\t18\t8\t9\nSCAFFOLD_3\t18\t40\t8\t9\n",
+    )
+    .unwrap();
+    ensure_fai(&fasta_path).unwrap();
+    assert_eq!(fs::read_to_string(&fai_path).unwrap(), FAI);
+
+    // Malformed, spaces rather than tabs
+    fs::write(&fai_path, FAI.replace('\t', " ")).unwrap();
+    ensure_fai(&fasta_path).unwrap();
+    assert_eq!(fs::read_to_string(&fai_path).unwrap(), FAI);
+
+    // A record missing from the index
+    fs::write(&fai_path, "SCAFFOLD_1\t16\t18\t8\t9\n").unwrap();
+    ensure_fai(&fasta_path).unwrap();
+    assert_eq!(fs::read_to_string(&fai_path).unwrap(), FAI);
+}
+
+#[test]
+fn check_bgzf_index_is_built() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa.gz");
+
+    // Two blocks, the second starting at SCAFFOLD_3
+    let (first, second) = FASTA.split_at(FASTA.find(">SCAFFOLD_3").unwrap());
+    let mut writer = bgzf::Writer::new(fs::File::create(&fasta_path).unwrap());
+    writer.write_all(first.as_bytes()).unwrap();
+    writer.flush().unwrap();
+    let block = writer.position();
+    writer.write_all(second.as_bytes()).unwrap();
+    writer.finish().unwrap();
+
+    assert_eq!(
+        bgzf_blocks(&fasta_path).unwrap(),
+        vec![(block, first.len() as u64)]
+    );
+
+    let mut reader = open_indexed_fasta(&fasta_path).unwrap();
+    assert!(dir.path().join("input.fa.gz.gzi").exists());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("input.fa.gz.fai")).unwrap(),
+        FAI
+    );
+    let record = reader.query(&"SCAFFOLD_3:15-18".parse().unwrap()).unwrap();
+    assert_eq!(record.sequence().as_ref(), b"CAGG");
+}
+
+#[test]
+fn check_parse_region() {
+    let index = noodles::fasta::fai::Index::from(vec![noodles::fasta::fai::Record::new(
+        "SCAFFOLD_1",
+        16,
+        18,
+        8,
+        9,
+    )]);
+    assert!(parse_region("SCAFFOLD_1", &index).is_ok());
+    assert!(parse_region("SCAFFOLD_1:5", &index).is_ok());
+    assert!(parse_region("SCAFFOLD_1:5-16", &index).is_ok());
+    assert_eq!(
+        parse_region("SCAFFOLD_1:5-17", &index).unwrap_err(),
+        "SCAFFOLD_1:5-17 is outside of SCAFFOLD_1 (1-16)"
+    );
+    assert_eq!(
+        parse_region("SCAFFOLD_2:1-5", &index).unwrap_err(),
+        "SCAFFOLD_2 is not in the fasta"
+    );
+}
+
+#[test]
+fn check_faidx_command() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa");
+    let region_file = dir.path().join("regions.txt");
+    let output = dir.path().join("regions.fa");
+    fs::write(&fasta_path, FASTA).unwrap();
+    fs::write(&region_file, "# from the file\nSCAFFOLD_1:7-10\n").unwrap();
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("faidx")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("SCAFFOLD_3")
+        .arg("-r")
+        .arg(&region_file)
+        .arg("-l")
+        .arg("10")
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(dir.path().join("input.fa.fai")).unwrap(),
+        FAI
+    );
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        ">SCAFFOLD_3\nAGTGTATTTT\nTATGCAGG\n>SCAFFOLD_1:7-10\nGCCG\n"
+    );
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("faidx")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("SCAFFOLD_3:10-30")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn check_curate_without_fai() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa");
+    let tpf_path = dir.path().join("input.tpf");
+    let output = dir.path().join("output.fa");
+    fs::write(&fasta_path, FASTA).unwrap();
+    fs::write(&tpf_path, "?\tSCAFFOLD_3:3-10\tRL_1\tPLUS\n").unwrap();
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .current_dir(dir.path())
+        .arg("curate")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("-t")
+        .arg(&tpf_path)
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), ">SUPER_1\nTGTATTTT\n");
+    assert!(dir.path().join("input.fa.fai").exists());
+}
+
+#[test]
+fn check_faidx_to_stdout_is_only_fasta() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa");
+    fs::write(&fasta_path, FASTA).unwrap();
+
+    let assert = Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("faidx")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("SCAFFOLD_1:7-10")
+        .assert()
+        .success();
+    let output = assert.get_output();
+    assert_eq!(
+        String::from_utf8(output.stdout.clone()).unwrap(),
+        ">SCAFFOLD_1:7-10\nGCCG\n"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("WELCOME TO Fasta Manipulator"));
+    assert!(stderr.contains("INDEX OF 2 RECORDS"));
+}
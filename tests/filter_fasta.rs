@@ -0,0 +1,121 @@
+use assert_cmd::Command;
+use regex::Regex;
+use std::fs;
+use std::io::BufReader;
+use tempfile::Builder;
+
+use fasta_manipulation::filter_fasta_mod::{filter_records, Filter, FilterMode, Rule};
+
+const FASTA: &str = ">SUPER_1\nACGTACGTAC\n>scaffold_2_ctg1\nACG\n>scaffold_3\nACGTAC\n>MT\nAC\n";
+
+fn run(filter: &Filter) -> (String, Vec<(String, usize)>, usize) {
+    let mut out: Vec<u8> = Vec::new();
+    let summary = filter_records(&mut BufReader::new(FASTA.as_bytes()), filter, &mut out).unwrap();
+    (
+        String::from_utf8(out).unwrap(),
+        summary.rules,
+        summary.too_short,
+    )
+}
+
+#[test]
+fn check_exclude_and_keep() {
+    let rules = vec![
+        Rule::Name("MT".to_string()),
+        Rule::Name("SUPER_9".to_string()),
+        Rule::Pattern(Regex::new("_ctg1$").unwrap()),
+    ];
+
+    let (fasta, counts, _) = run(&Filter {
+        mode: FilterMode::Exclude,
+        rules: rules.to_owned(),
+        min_length: None,
+    });
+    assert_eq!(fasta, ">SUPER_1\nACGTACGTAC\n>scaffold_3\nACGTAC\n");
+    assert_eq!(
+        counts,
+        vec![
+            ("name MT".to_string(), 1),
+            ("name SUPER_9".to_string(), 0),
+            ("regex _ctg1$".to_string(), 1),
+        ]
+    );
+
+    let (fasta, _, _) = run(&Filter {
+        mode: FilterMode::Keep,
+        rules,
+        min_length: None,
+    });
+    assert_eq!(fasta, ">scaffold_2_ctg1\nACG\n>MT\nAC\n");
+}
+
+#[test]
+fn check_prefix_and_min_length() {
+    // Length is applied in either mode, a record can match more than one rule
+    let (fasta, counts, too_short) = run(&Filter {
+        mode: FilterMode::Keep,
+        rules: vec![
+            Rule::Prefix("scaffold_".to_string()),
+            Rule::Name("scaffold_3".to_string()),
+        ],
+        min_length: Some(5),
+    });
+    assert_eq!(fasta, ">scaffold_3\nACGTAC\n");
+    assert_eq!(counts[0].1, 2);
+    assert_eq!(counts[1].1, 1);
+    assert_eq!(too_short, 2);
+
+    let (fasta, _, _) = run(&Filter {
+        mode: FilterMode::Exclude,
+        rules: Vec::new(),
+        min_length: Some(5),
+    });
+    assert_eq!(fasta, ">SUPER_1\nACGTACGTAC\n>scaffold_3\nACGTAC\n");
+}
+
+#[test]
+fn check_filterfasta_command() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa");
+    let names = dir.path().join("names.lst");
+    let output = dir.path().join("output.fa");
+    fs::write(&fasta_path, FASTA).unwrap();
+    fs::write(&names, "SUPER_1\n\nSUPER_2\n").unwrap();
+
+    let assert = Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("filterfasta")
+        .arg(&fasta_path)
+        .arg("-m")
+        .arg("keep")
+        .arg("-f")
+        .arg(&names)
+        .arg("-e")
+        .arg("^MT$")
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.to_owned()).unwrap();
+    assert!(stdout.contains("name SUPER_2:\t0"));
+    assert!(stdout.contains("WARNING: 1 MATCHED NOTHING:"));
+
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        ">SUPER_1\nACGTACGTAC\n>MT\nAC\n"
+    );
+
+    // Nothing to keep by
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("filterfasta")
+        .arg(&fasta_path)
+        .arg("-m")
+        .arg("keep")
+        .arg("-n")
+        .arg("5")
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .failure();
+}
@@ -0,0 +1,64 @@
+use assert_cmd::Command;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use tempfile::Builder;
+
+use fasta_manipulation::gencsv_mod::check_data_type;
+
+fn write_file(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    write!(File::create(path).unwrap(), "{}", contents).unwrap();
+}
+
+#[test]
+fn check_data_type_matches_folder() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let pep = dir.path().join("pep.fa");
+    let cds = dir.path().join("cds.fa");
+    write_file(&pep, ">P1\nMKVLAAGIV*\n");
+    write_file(&cds, ">C1\nATGAAAGTNCTG\n");
+
+    assert_eq!(check_data_type(&pep, "pep").unwrap(), 1);
+    assert_eq!(check_data_type(&cds, "cds").unwrap(), 1);
+    assert!(check_data_type(&pep, "cdna").is_err());
+    assert!(check_data_type(&cds, "pep").is_err());
+
+    let empty = dir.path().join("empty.fa");
+    write_file(&empty, "");
+    assert!(check_data_type(&empty, "rna").is_err());
+}
+
+#[test]
+fn check_geneset_csvs_command() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let root = dir.path();
+    let accession = root.join("insect/ApisMellifera/ApisMellifera.AMel1");
+    write_file(&accession.join("pep/good.fa"), ">P1\nMKVLAAGIV\n");
+    write_file(&accession.join("pep/bad.fa"), ">P1\nACGTACGT\n");
+    write_file(&accession.join("cds/good_cds.fa"), ">C1\nATGAAA\n");
+    write_file(
+        &root.join("bird/GallusGallus/GallusGallus.GRCg7b/rna/rna.fa"),
+        ">R1\nAUGC\n",
+    );
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("geneset_csvs")
+        .arg("-d")
+        .arg(root)
+        .arg("-c")
+        .arg("insect")
+        .assert()
+        .success();
+
+    let csv =
+        fs::read_to_string(root.join("insect/csv_data/ApisMellifera.AMel1-data.csv")).unwrap();
+    assert!(csv.starts_with("org,type,data_file"));
+    assert!(csv.contains("good.fa"));
+    assert!(csv.contains("good_cds.fa"));
+    assert!(!csv.contains("bad.fa"));
+
+    // Only the requested clade is refreshed
+    assert!(!root.join("bird/csv_data").exists());
+}
@@ -0,0 +1,351 @@
+use assert_cmd::Command;
+use flate2::write::GzEncoder;
+use flate2::Compression as GzLevel;
+use noodles::bgzf;
+use noodles::core::Region;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use tempfile::Builder;
+
+use fasta_manipulation::{
+    detect_compression, open_indexed_fasta, open_reader, output_compression, validate_fasta,
+    write_fasta, Compression, FastaOutput,
+};
+
+const FASTA: &str = ">SCAFFOLD_1\nATGCATGCCGTATAGA\n>SCAFFOLD_3\nAGTGTATTTTTATGCA\n";
+const FAI: &str = "SCAFFOLD_1\t16\t12\t16\t17\nSCAFFOLD_3\t16\t41\t16\t17\n";
+
+fn write_gzip(path: &Path, contents: &str) {
+    let mut encoder = GzEncoder::new(File::create(path).unwrap(), GzLevel::default());
+    encoder.write_all(contents.as_bytes()).unwrap();
+    encoder.finish().unwrap();
+}
+
+fn write_bgzf(path: &Path, records: &[&str]) {
+    // One BGZF block per record, with the .gzi of where each block starts
+    // (compressed offset, uncompressed offset) after the first
+    let mut writer = bgzf::Writer::new(File::create(path).unwrap());
+    let mut offsets: Vec<(u64, u64)> = Vec::new();
+    let mut uncompressed: u64 = 0;
+    for (index, record) in records.iter().enumerate() {
+        if index > 0 {
+            offsets.push((writer.position(), uncompressed));
+        }
+        writer.write_all(record.as_bytes()).unwrap();
+        writer.flush().unwrap();
+        uncompressed += record.len() as u64;
+    }
+    writer.finish().unwrap();
+
+    let mut gzi = File::create(format!("{}.gzi", path.display())).unwrap();
+    gzi.write_all(&(offsets.len() as u64).to_le_bytes())
+        .unwrap();
+    for (compressed, uncompressed) in offsets {
+        gzi.write_all(&compressed.to_le_bytes()).unwrap();
+        gzi.write_all(&uncompressed.to_le_bytes()).unwrap();
+    }
+}
+
+#[test]
+fn check_detect_compression() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let plain = dir.path().join("input.fa");
+    let gzip = dir.path().join("input.fa.gz");
+    let bgzip = dir.path().join("input.bgz.fa.gz");
+    // Named .gz but not compressed, the magic bytes win
+    let lying = dir.path().join("plain.fa.gz");
+
+    fs::write(&plain, FASTA).unwrap();
+    fs::write(&lying, FASTA).unwrap();
+    write_gzip(&gzip, FASTA);
+    write_bgzf(&bgzip, &[FASTA]);
+
+    assert_eq!(detect_compression(&plain).unwrap(), Compression::None);
+    assert_eq!(detect_compression(&lying).unwrap(), Compression::None);
+    assert_eq!(detect_compression(&gzip).unwrap(), Compression::Gzip);
+    assert_eq!(detect_compression(&bgzip).unwrap(), Compression::Bgzf);
+
+    for path in [&plain, &lying, &gzip, &bgzip] {
+        let mut contents = String::new();
+        open_reader(path)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, FASTA);
+    }
+}
+
+#[test]
+fn check_validate_compressed_fasta() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let gzip = dir.path().join("input.fa.gz");
+    let bgzip = dir.path().join("input.fa.bgz");
+    write_gzip(&gzip, FASTA);
+    write_bgzf(&bgzip, &[FASTA]);
+
+    for path in [&gzip, &bgzip] {
+        let lengths = validate_fasta(path.to_str().unwrap()).unwrap();
+        assert_eq!(lengths.len(), 2);
+        assert_eq!(lengths["SCAFFOLD_3"], 16);
+    }
+}
+
+#[test]
+fn check_indexed_bgzf_fasta() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let bgzip = dir.path().join("input.fa.gz");
+    let gzip = dir.path().join("gzip.fa.gz");
+
+    let (first, second) = FASTA.split_at(FASTA.find(">SCAFFOLD_3").unwrap());
+    write_bgzf(&bgzip, &[first, second]);
+    fs::write(format!("{}.fai", bgzip.display()), FAI).unwrap();
+    write_gzip(&gzip, FASTA);
+    fs::write(format!("{}.fai", gzip.display()), FAI).unwrap();
+
+    // Reading from the second block needs the .gzi
+    let mut reader = open_indexed_fasta(&bgzip).unwrap();
+    let region: Region = "SCAFFOLD_3:3-6".parse().unwrap();
+    let record = reader.query(&region).unwrap();
+    assert_eq!(record.sequence().as_ref(), b"TGTA");
+
+    // Plain gzip can only be read from the start
+    let error = open_indexed_fasta(&gzip).err().unwrap();
+    assert!(error.to_string().contains("bgzip"));
+}
+
+#[test]
+fn check_curate_bgzf_fasta() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input_fasta.fa.gz");
+    let tpf_path = dir.path().join("input.tpf");
+    let output = dir.path().join("output.fa");
+
+    let (first, second) = FASTA.split_at(FASTA.find(">SCAFFOLD_3").unwrap());
+    write_bgzf(&fasta_path, &[first, second]);
+    fs::write(format!("{}.fai", fasta_path.display()), FAI).unwrap();
+    fs::write(
+        &tpf_path,
+        "?\tSCAFFOLD_1:1-9\tRL_1\tMINUS\n\
+         GAP\tTYPE-2\t5\n\
+         ?\tSCAFFOLD_3:1-5\tRL_1\tPLUS\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .current_dir(dir.path())
+        .arg("curate")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("-t")
+        .arg(&tpf_path)
+        .arg("-o")
+        .arg(&output)
+        .arg("5")
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        ">SUPER_1\nGGCATGCATNNNNNAGTGT\n"
+    );
+}
+
+#[test]
+fn check_gzip_fasta_commands() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let gzip = dir.path().join("input.fa.gz");
+    write_gzip(&gzip, FASTA);
+
+    let filtered = dir.path().join("filtered.fa");
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("filterfasta")
+        .arg(&gzip)
+        .arg("-o")
+        .arg(&filtered)
+        .arg("-l")
+        .arg("SCAFFOLD_1")
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&filtered).unwrap(),
+        ">SCAFFOLD_3\nAGTGTATTTTTATGCA\n"
+    );
+
+    let mapped = format!("{}/", dir.path().display());
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("mapheaders")
+        .arg("-f")
+        .arg(&gzip)
+        .arg("-o")
+        .arg(&mapped)
+        .assert()
+        .success();
+    let mapped_fasta = fs::read_to_string(dir.path().join("mapped.fasta")).unwrap();
+    assert_eq!(mapped_fasta.matches('>').count(), 2);
+    assert!(mapped_fasta.contains("AGTGTATTTTTATGCA\n"));
+}
+
+#[test]
+fn check_bgzf_output_is_indexed() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let output = dir.path().join("output.fa.gz");
+    let path = output.to_str().unwrap();
+
+    // Enough sequence to run over several BGZF blocks
+    let mut expected = String::new();
+    let mut file = FastaOutput::create(path, output_compression(path, None)).unwrap();
+    for index in 1..=4 {
+        let sequence: String = "ACGTTGCA".repeat(5000 * index);
+        let record = format!(">SCAFFOLD_{} some description\n", index);
+        file.write_all(record.as_bytes()).unwrap();
+        for line in sequence.as_bytes().chunks(60) {
+            file.write_all(line).unwrap();
+            file.write_all(b"\n").unwrap();
+        }
+        expected.push_str(&record);
+        expected.push_str(&sequence);
+    }
+    file.finish().unwrap();
+
+    assert_eq!(detect_compression(&output).unwrap(), Compression::Bgzf);
+    let fai = fs::read_to_string(format!("{}.fai", path)).unwrap();
+    assert_eq!(fai.lines().next().unwrap(), "SCAFFOLD_1\t40000\t29\t60\t61");
+    assert_eq!(fai.lines().count(), 4);
+    let gzi = fs::read(format!("{}.gzi", path)).unwrap();
+    assert!(u64::from_le_bytes(gzi[..8].try_into().unwrap()) > 1);
+
+    // Straight back in through the .fai and .gzi
+    let mut reader = open_indexed_fasta(&output).unwrap();
+    let region: Region = "SCAFFOLD_4:159993-160000".parse().unwrap();
+    let record = reader.query(&region).unwrap();
+    assert_eq!(record.sequence().as_ref(), b"ACGTTGCA");
+
+    let mut contents = String::new();
+    open_reader(&output)
+        .unwrap()
+        .read_to_string(&mut contents)
+        .unwrap();
+    assert_eq!(
+        contents.replace('\n', "").len(),
+        expected.replace('\n', "").len()
+    );
+}
+
+#[test]
+fn check_compressed_output_commands() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input_fasta.fa");
+    let tpf_path = dir.path().join("input.tpf");
+    let output = dir.path().join("curated.fa.gz");
+
+    fs::write(&fasta_path, FASTA).unwrap();
+    fs::write(format!("{}.fai", fasta_path.display()), FAI).unwrap();
+    fs::write(
+        &tpf_path,
+        "?\tSCAFFOLD_1:1-9\tRL_1\tMINUS\n\
+         GAP\tTYPE-2\t5\n\
+         ?\tSCAFFOLD_3:1-5\tRL_1\tPLUS\n",
+    )
+    .unwrap();
+
+    // Bgzipped from the extension, the other outputs lose the .fa.gz
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .current_dir(dir.path())
+        .arg("curate")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("-t")
+        .arg(&tpf_path)
+        .arg("-o")
+        .arg(&output)
+        .arg("5")
+        .assert()
+        .success();
+
+    assert_eq!(detect_compression(&output).unwrap(), Compression::Bgzf);
+    let mut contents = String::new();
+    open_reader(&output)
+        .unwrap()
+        .read_to_string(&mut contents)
+        .unwrap();
+    assert_eq!(contents, ">SUPER_1\nGGCATGCATNNNNNAGTGT\n");
+    assert_eq!(
+        fs::read_to_string(format!("{}.fai", output.display())).unwrap(),
+        "SUPER_1\t19\t9\t19\t20\n"
+    );
+    assert!(dir.path().join("curated.agp").exists());
+    assert!(dir.path().join("curated.debug.tsv").exists());
+
+    // Split chunks named .gz when asked for gzip
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("splitbycount")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("-o")
+        .arg(dir.path())
+        .arg("-d")
+        .arg("other")
+        .arg("-s")
+        .arg("false")
+        .arg("-c")
+        .arg("1")
+        .arg("-z")
+        .arg("gzip")
+        .assert()
+        .success();
+
+    let chunk = dir
+        .path()
+        .join("input_fasta/other/input_fasta_f1_c1-a1.fa.gz");
+    assert_eq!(detect_compression(&chunk).unwrap(), Compression::Gzip);
+    let lengths = validate_fasta(chunk.to_str().unwrap()).unwrap();
+    assert_eq!(lengths["SCAFFOLD_1"], 16);
+}
+
+#[test]
+fn check_write_fasta_returns_errors() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let outdir = dir.path().to_string_lossy().to_string();
+    // The output name is taken by a directory
+    fs::create_dir(dir.path().join("taken.fa")).unwrap();
+
+    assert!(write_fasta(&outdir, "taken.fa".to_string(), vec![], Compression::None).is_err());
+    assert!(write_fasta(&outdir, "free.fa".to_string(), vec![], Compression::None).is_ok());
+}
+
+#[test]
+fn check_split_commands_fail_on_unwritable_output() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input_fasta.fa");
+    fs::write(&fasta_path, FASTA).unwrap();
+    fs::write(format!("{}.fai", fasta_path.display()), FAI).unwrap();
+    // The output directory is already a file
+    let blocked = dir.path().join("blocked");
+    fs::write(&blocked, "").unwrap();
+
+    for (command, options) in [
+        ("splitbycount", ["-c", "1", "-s", "false"]),
+        ("splitbysize", ["-m", "1", "-s", "false"]),
+    ] {
+        let assert = Command::cargo_bin("fasta_manipulation")
+            .unwrap()
+            .arg(command)
+            .arg("-f")
+            .arg(&fasta_path)
+            .arg("-o")
+            .arg(&blocked)
+            .arg("-d")
+            .arg("other")
+            .args(options)
+            .assert()
+            .failure();
+        assert!(String::from_utf8_lossy(&assert.get_output().stderr)
+            .contains("Something is wrong with the file!"));
+    }
+}
@@ -0,0 +1,240 @@
+use assert_cmd::Command;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::Write;
+use tempfile::Builder;
+
+use fasta_manipulation::liftover_mod::{feature_columns, lift_features, LiftMap, Lifted};
+use fasta_manipulation::tpf_fasta_mod::parse_tpf_entries;
+
+// SUPER_1 = SCAFFOLD_1:1-9 (MINUS) + 200 N's + SCAFFOLD_2:1-11
+// SUPER_2 = SCAFFOLD_3:1-5, SUPER_3_unloc_1 = SCAFFOLD_2:12-20
+// SCAFFOLD_3 = SCAFFOLD_3:6-10
+fn small_lift_map() -> LiftMap {
+    let entries =
+        parse_tpf_entries(&"test_data/iyAndFlav1/small/small_test.curated.tpf".to_string());
+    LiftMap::new(&entries, 200)
+}
+
+#[test]
+fn check_lift_intervals() {
+    let lift_map = small_lift_map();
+    assert_eq!(lift_map.scaffold_lengths["SUPER_1"], 220);
+
+    assert_eq!(
+        lift_map.lift("SCAFFOLD_1", 2, 4).unwrap(),
+        Lifted {
+            scaffold: "SUPER_1".to_string(),
+            start: 6,
+            end: 8,
+            flipped: true
+        }
+    );
+    assert_eq!(
+        lift_map.lift("SCAFFOLD_2", 3, 5).unwrap(),
+        Lifted {
+            scaffold: "SUPER_1".to_string(),
+            start: 212,
+            end: 214,
+            flipped: false
+        }
+    );
+    assert_eq!(
+        lift_map.lift("SCAFFOLD_2", 10, 13).unwrap_err(),
+        "spans a break, lands in SUPER_1:219-220 | SUPER_3_unloc_1:1-2"
+    );
+    assert_eq!(
+        lift_map.lift("SCAFFOLD_1", 8, 12).unwrap_err(),
+        "SCAFFOLD_1:10-12 was dropped in curation"
+    );
+    assert_eq!(
+        lift_map.lift("SCAFFOLD_9", 1, 2).unwrap_err(),
+        "SCAFFOLD_9 is not in the layout"
+    );
+}
+
+#[test]
+fn check_lift_features_bed_gff3_paf() {
+    let lift_map = small_lift_map();
+
+    let bed =
+        "track name=genes\nSCAFFOLD_1\t1\t4\tgene1\t0\t+\t1\t3\nSCAFFOLD_2\t9\t13\tgene2\t0\t-\n";
+    let mut lifted: Vec<u8> = Vec::new();
+    let mut unlifted: Vec<u8> = Vec::new();
+    let counts = lift_features(
+        &mut bed.as_bytes(),
+        "bed",
+        &feature_columns("bed", "target").unwrap(),
+        &lift_map,
+        &mut lifted,
+        &mut unlifted,
+    )
+    .unwrap();
+    assert_eq!(counts, (1, 1));
+    // SCAFFOLD_1:2-4 is SUPER_1:6-8 on the other strand, thick 2-3 is 7-8
+    assert_eq!(
+        String::from_utf8(lifted).unwrap(),
+        "track name=genes\nSUPER_1\t5\t8\tgene1\t0\t-\t6\t8\n"
+    );
+    assert!(String::from_utf8(unlifted)
+        .unwrap()
+        .starts_with("spans a break"));
+
+    let gff = "##gff-version 3\n##sequence-region SCAFFOLD_2 1 74\nSCAFFOLD_2\tsrc\tgene\t3\t5\t.\t+\t.\tID=g1\n##FASTA\n>SCAFFOLD_2\n";
+    let mut lifted: Vec<u8> = Vec::new();
+    lift_features(
+        &mut gff.as_bytes(),
+        "gff3",
+        &feature_columns("gff3", "target").unwrap(),
+        &lift_map,
+        &mut lifted,
+        &mut Vec::new(),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(lifted).unwrap(),
+        "##gff-version 3\nSUPER_1\tsrc\tgene\t212\t214\t.\t+\t.\tID=g1\n"
+    );
+
+    let paf = "read1\t100\t0\t3\t+\tSCAFFOLD_1\t74\t1\t4\t3\t3\t60\n";
+    let mut lifted: Vec<u8> = Vec::new();
+    lift_features(
+        &mut paf.as_bytes(),
+        "paf",
+        &feature_columns("paf", "target").unwrap(),
+        &lift_map,
+        &mut lifted,
+        &mut Vec::new(),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(lifted).unwrap(),
+        "read1\t100\t0\t3\t-\tSUPER_1\t220\t5\t8\t3\t3\t60\n"
+    );
+}
+
+#[test]
+fn check_lift_bed12_blocks() {
+    let lift_map = small_lift_map();
+
+    // SCAFFOLD_1:2-4 is flipped, so are its blocks. The second
+    // line's block runs past the end of the feature
+    let bed = "SCAFFOLD_1\t1\t4\tgene1\t0\t+\t1\t4\t0\t2\t1,2,\t0,1,\n\
+               SCAFFOLD_1\t1\t4\tgene2\t0\t+\t1\t4\t0\t1\t2,\t2,\n";
+    let mut lifted: Vec<u8> = Vec::new();
+    let mut unlifted: Vec<u8> = Vec::new();
+    let counts = lift_features(
+        &mut bed.as_bytes(),
+        "bed",
+        &feature_columns("bed", "target").unwrap(),
+        &lift_map,
+        &mut lifted,
+        &mut unlifted,
+    )
+    .unwrap();
+    assert_eq!(counts, (1, 1));
+    assert_eq!(
+        String::from_utf8(lifted).unwrap(),
+        "SUPER_1\t5\t8\tgene1\t0\t-\t5\t8\t0\t2\t2,1,\t0,2,\n"
+    );
+    assert!(String::from_utf8(unlifted)
+        .unwrap()
+        .starts_with("block 2-4 is outside of the 3 bp feature\tSCAFFOLD_1\t1\t4\tgene2"));
+}
+
+#[test]
+fn check_liftover_command() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let busco = dir.path().join("full_table.tsv");
+    let output = dir.path().join("lifted.tsv");
+
+    write!(
+        File::create(&busco).unwrap(),
+        "# Busco id\tStatus\tSequence\tGene Start\tGene End\tStrand\tScore\tLength\n\
+         1at1\tComplete\tSCAFFOLD_3\t7\t9\t+\t10.0\t3\n\
+         2at1\tMissing\n\
+         3at1\tComplete\tSCAFFOLD_3\t10\t20\t-\t10.0\t11\n"
+    )
+    .unwrap();
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("liftover")
+        .arg("-t")
+        .arg("test_data/iyAndFlav1/small/small_test.curated.tpf")
+        .arg("-i")
+        .arg(&busco)
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+
+    let lifted = fs::read_to_string(&output).unwrap();
+    assert!(lifted.contains("1at1\tComplete\tSCAFFOLD_3\t2\t4\t+\t10.0\t3\n"));
+    assert!(lifted.contains("2at1\tMissing\n"));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("lifted.unlifted.tsv")).unwrap(),
+        "SCAFFOLD_3:11-20 was dropped in curation\t3at1\tComplete\tSCAFFOLD_3\t10\t20\t-\t10.0\t11\n"
+    );
+}
+
+#[test]
+fn check_liftover_gzipped_input() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let bed = dir.path().join("features.bed.gz");
+    let output = dir.path().join("lifted.bed");
+
+    let mut encoder = GzEncoder::new(File::create(&bed).unwrap(), Compression::default());
+    encoder.write_all(b"SCAFFOLD_3\t6\t9\tgeneA\n").unwrap();
+    encoder.finish().unwrap();
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("liftover")
+        .arg("-t")
+        .arg("test_data/iyAndFlav1/small/small_test.curated.tpf")
+        .arg("-i")
+        .arg(&bed)
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        "SCAFFOLD_3\t1\t4\tgeneA\n"
+    );
+}
+
+#[test]
+fn check_liftover_naming_as_curate() {
+    // -p and -m as they were given to curate
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let bed = dir.path().join("features.bed");
+    let table = dir.path().join("chromosomes.tsv");
+    let output = dir.path().join("lifted.bed");
+    fs::write(&bed, "SCAFFOLD_2\t14\t18\tgeneA\nSCAFFOLD_3\t0\t2\tgeneB\n").unwrap();
+    fs::write(&table, "RL_3\tZ\n").unwrap();
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("liftover")
+        .arg("-t")
+        .arg("test_data/iyAndFlav1/small/small_test.curated.tpf")
+        .arg("-i")
+        .arg(&bed)
+        .arg("-o")
+        .arg(&output)
+        .arg("-p")
+        .arg("CHR")
+        .arg("-m")
+        .arg(&table)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        "CHR_Z_unloc_1\t3\t7\tgeneA\nCHR_2\t0\t2\tgeneB\n"
+    );
+}
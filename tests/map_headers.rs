@@ -0,0 +1,422 @@
+use assert_cmd::Command;
+use std::fs;
+use std::path::Path;
+use tempfile::Builder;
+
+use regex::Regex;
+
+use fasta_manipulation::mapping_headers::{
+    check_names, order_headers, read_mapping, HeaderRewriter, HeaderTemplate, MapOrder, OldHeader,
+    Unmapped,
+};
+
+const FASTA: &str =
+    ">scaffold_b\nACGT\n>scaffold_a\nACGTACGT\n>scaffold_c\nACGTACGT\n>scaffold_d\nA\n";
+
+fn mapping_pairs(path: &Path) -> String {
+    // The mapping without its # header
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .filter(|x| !x.starts_with('#'))
+        .map(|x| format!("{}\n", x))
+        .collect()
+}
+
+fn header(name: &str, description: Option<&str>, length: usize) -> OldHeader {
+    OldHeader {
+        name: name.to_string(),
+        description: description.map(|x| x.to_string()),
+        length,
+    }
+}
+
+fn names(headers: Vec<OldHeader>) -> Vec<String> {
+    headers.into_iter().map(|x| x.name).collect()
+}
+
+#[test]
+fn check_order_headers() {
+    let headers = vec![
+        header("scaffold_b", None, 4),
+        header("scaffold_a", None, 8),
+        header("scaffold_c", None, 8),
+    ];
+    assert_eq!(
+        names(order_headers(headers.clone(), MapOrder::Input).unwrap()),
+        vec!["scaffold_b", "scaffold_a", "scaffold_c"]
+    );
+    // Equal lengths keep their input order
+    assert_eq!(
+        names(order_headers(headers.clone(), MapOrder::Length).unwrap()),
+        vec!["scaffold_a", "scaffold_c", "scaffold_b"]
+    );
+
+    let mut duplicated = headers;
+    duplicated.push(header("scaffold_b", None, 2));
+    assert_eq!(
+        order_headers(duplicated, MapOrder::Input).unwrap_err(),
+        "scaffold_b is in the fasta more than once"
+    );
+}
+
+#[test]
+fn check_header_template() {
+    let old = header("scaffold_12", Some("gene=ABC1 chr=3"), 1500);
+
+    let template = HeaderTemplate::new("{prefix}_{idx:06}", "FMMH").unwrap();
+    assert_eq!(template.render(7, &old).unwrap(), "FMMH_000007");
+    let template = HeaderTemplate::new("{name}.{len}", "FMMH").unwrap();
+    assert_eq!(template.render(7, &old).unwrap(), "scaffold_12.1500");
+
+    let mut template = HeaderTemplate::new("{cap:1}|{len}|{type}", "FMMH").unwrap();
+    assert_eq!(
+        template.render(0, &old).unwrap_err(),
+        "{cap:1} needs a regex (-e)"
+    );
+    template.pattern = Some(Regex::new(r"gene=(\S+)").unwrap());
+    template.data_type = Some("cdna".to_string());
+    assert_eq!(template.render(0, &old).unwrap(), "ABC1|1500|cdna");
+    assert_eq!(
+        template
+            .render(0, &header("scaffold_13", None, 1))
+            .unwrap_err(),
+        "scaffold_13 does not match gene=(\\S+)"
+    );
+
+    assert_eq!(
+        HeaderTemplate::new("{prefix}_{number}", "FMMH").unwrap_err(),
+        "{number} is not a template field"
+    );
+    assert!(HeaderTemplate::new("{prefix}_{idx", "FMMH").is_err());
+    assert!(HeaderTemplate::new("{idx:six}", "FMMH").is_err());
+}
+
+#[test]
+fn check_names_are_legal() {
+    let names = |x: &[&str]| x.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+    assert!(check_names(&names(&["FMMH_1", "ABC1|1500", "chr1.2"]), 20).is_ok());
+    assert_eq!(
+        check_names(&names(&["FMMH_1", "FMMH_1"]), 20).unwrap_err(),
+        "FMMH_1 would be given to more than one sequence"
+    );
+    assert_eq!(
+        check_names(&names(&["gene ABC1"]), 20).unwrap_err(),
+        "'gene ABC1' is not a legal sequence name"
+    );
+    assert!(check_names(&names(&["*star"]), 20).is_err());
+    assert!(check_names(&names(&[""]), 20).is_err());
+    assert_eq!(
+        check_names(&names(&["FMMH_000001"]), 6).unwrap_err(),
+        "FMMH_000001 is longer than 6 characters"
+    );
+}
+
+#[test]
+fn check_mapheaders_is_reproducible() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa");
+    fs::write(&fasta_path, FASTA).unwrap();
+
+    let run = |order: &str| {
+        let output = format!("{}/", dir.path().display());
+        Command::cargo_bin("fasta_manipulation")
+            .unwrap()
+            .arg("mapheaders")
+            .arg("-f")
+            .arg(&fasta_path)
+            .arg("-o")
+            .arg(&output)
+            .arg("-s")
+            .arg(order)
+            .assert()
+            .success();
+        (
+            mapping_pairs(&dir.path().join("mapped-heads.tsv")),
+            fs::read_to_string(dir.path().join("mapped.fasta")).unwrap(),
+        )
+    };
+
+    let (mapping, mapped) = run("input");
+    assert_eq!(
+        mapping,
+        "scaffold_b\tFMMH_0\nscaffold_a\tFMMH_1\nscaffold_c\tFMMH_2\nscaffold_d\tFMMH_3\n"
+    );
+    assert_eq!(
+        mapped,
+        ">FMMH_0\nACGT\n>FMMH_1\nACGTACGT\n>FMMH_2\nACGTACGT\n>FMMH_3\nA\n"
+    );
+    assert_eq!(run("input"), (mapping, mapped));
+
+    // Longest first, the fasta itself keeps its order
+    let (mapping, mapped) = run("length");
+    assert_eq!(
+        mapping,
+        "scaffold_a\tFMMH_0\nscaffold_c\tFMMH_1\nscaffold_b\tFMMH_2\nscaffold_d\tFMMH_3\n"
+    );
+    assert_eq!(
+        mapped,
+        ">FMMH_2\nACGT\n>FMMH_0\nACGTACGT\n>FMMH_1\nACGTACGT\n>FMMH_3\nA\n"
+    );
+}
+
+#[test]
+fn check_header_rewriter() {
+    let fasta = ">FMMH_0 first record\nACGT\n>new_scaffold\nGG\n>FMMH_1\nCC\n";
+    let mapping = || {
+        vec![
+            ("FMMH_0".to_string(), "scaffold_b".to_string()),
+            ("FMMH_1".to_string(), "scaffold_a".to_string()),
+        ]
+    };
+
+    // Descriptions are dropped unless asked for, unmapped is an error
+    let mut rewriter = HeaderRewriter::new(mapping()).unwrap();
+    let mut out: Vec<u8> = Vec::new();
+    rewriter
+        .rewrite(&mut ">FMMH_0 first record\nACGT\n".as_bytes(), &mut out)
+        .unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), ">scaffold_b\nACGT\n");
+    assert_eq!(
+        rewriter
+            .rewrite(&mut fasta.as_bytes(), &mut Vec::new())
+            .unwrap_err()
+            .to_string(),
+        "new_scaffold is not in the mapping"
+    );
+
+    let mut rewriter = HeaderRewriter::new(mapping()).unwrap();
+    rewriter.keep_description = true;
+    rewriter.unmapped = Unmapped::Keep;
+    let mut out: Vec<u8> = Vec::new();
+    let summary = rewriter.rewrite(&mut fasta.as_bytes(), &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        ">scaffold_b first record\nACGT\n>new_scaffold\nGG\n>scaffold_a\nCC\n"
+    );
+    assert_eq!(summary.renamed, 2);
+    assert_eq!(summary.kept, vec!["new_scaffold"]);
+
+    // Auto names skip any already used by the mapping
+    let mut rewriter =
+        HeaderRewriter::new(vec![("FMMH_0".to_string(), "FMMH_1".to_string())]).unwrap();
+    rewriter.unmapped = Unmapped::Auto;
+    let mut out: Vec<u8> = Vec::new();
+    let summary = rewriter.rewrite(&mut fasta.as_bytes(), &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        ">FMMH_1\nACGT\n>FMMH_2\nGG\n>FMMH_3\nCC\n"
+    );
+    assert_eq!(
+        summary.assigned,
+        vec![
+            ("new_scaffold".to_string(), "FMMH_2".to_string()),
+            ("FMMH_1".to_string(), "FMMH_3".to_string())
+        ]
+    );
+
+    let shared = || {
+        vec![
+            ("a".to_string(), "x".to_string()),
+            ("b".to_string(), "x".to_string()),
+        ]
+    };
+    assert_eq!(
+        HeaderRewriter::new(shared()).unwrap_err(),
+        "x is mapped to more than once"
+    );
+}
+
+#[test]
+fn check_map_and_remap_round_trip() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa");
+    fs::write(
+        &fasta_path,
+        ">scaffold_b some description\nACGT\n>scaffold_a\nACGTACGT\n",
+    )
+    .unwrap();
+    let output = format!("{}/", dir.path().display());
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("mapheaders")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(dir.path().join("mapped.fasta")).unwrap(),
+        ">FMMH_0\nACGT\n>FMMH_1\nACGTACGT\n"
+    );
+
+    // A record added after mapping
+    let mut mapped = fs::read_to_string(dir.path().join("mapped.fasta")).unwrap();
+    mapped.push_str(">extra\nTT\n");
+    fs::write(dir.path().join("mapped.fasta"), mapped).unwrap();
+
+    let remap = |unmapped: &str, check: &str| {
+        Command::cargo_bin("fasta_manipulation")
+            .unwrap()
+            .arg("remapheaders")
+            .arg("-f")
+            .arg(dir.path().join("mapped.fasta"))
+            .arg("-m")
+            .arg(dir.path().join("mapped-heads.tsv"))
+            .arg("-o")
+            .arg(dir.path().join("remapped"))
+            .arg("-u")
+            .arg(unmapped)
+            .arg("-c")
+            .arg(check)
+            .assert()
+    };
+    // No longer the fasta the mapping was made with
+    remap("auto", "true").failure();
+    remap("error", "false").failure();
+    remap("auto", "false").success();
+    assert_eq!(
+        fs::read_to_string(dir.path().join("remapped_OH.fasta")).unwrap(),
+        ">scaffold_b\nACGT\n>scaffold_a\nACGTACGT\n>UNMAPPED_2\nTT\n"
+    );
+    assert_eq!(
+        mapping_pairs(&dir.path().join("remapped_OH.added-heads.tsv")),
+        "extra\tUNMAPPED_2\n"
+    );
+}
+
+#[test]
+fn check_mapheaders_template() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa");
+    fs::write(
+        &fasta_path,
+        ">tx1 gene=ABC1\nACGT\n>tx2 gene=ABC1\nACGTACGT\n>tx3 gene=XYZ\nA\n",
+    )
+    .unwrap();
+    let output = format!("{}/", dir.path().display());
+    let run = |template: &str| {
+        Command::cargo_bin("fasta_manipulation")
+            .unwrap()
+            .arg("mapheaders")
+            .arg("-f")
+            .arg(&fasta_path)
+            .arg("-o")
+            .arg(&output)
+            .arg("-t")
+            .arg(template)
+            .arg("-e")
+            .arg(r"gene=(\S+)")
+            .assert()
+    };
+
+    run("{cap:1}|{idx:03}").success();
+    assert_eq!(
+        mapping_pairs(&dir.path().join("mapped-heads.tsv")),
+        "tx1\tABC1|000\ntx2\tABC1|001\ntx3\tXYZ|002\n"
+    );
+
+    // Two sequences would be called ABC1
+    run("{cap:1}").failure();
+}
+
+#[test]
+fn check_read_mapping() {
+    let read = |x: &str| read_mapping(&mut x.as_bytes());
+    let header = "#fasman-mapping\tversion=1\tfasman=0.1.5\n\
+                  #source\tinput.fa\tmd5=0123456789abcdef0123456789abcdef\n\
+                  #mapped\tmapped.fasta\tmd5=fedcba9876543210fedcba9876543210\n\
+                  #old-header\tnew-header\n";
+
+    let mapping = read(&format!("{}a\tFMMH_0\nb\tFMMH_1\n", header)).unwrap();
+    assert_eq!(mapping.pairs.len(), 2);
+    let mapping_header = mapping.header.unwrap();
+    assert_eq!(mapping_header.version, 1);
+    assert_eq!(
+        mapping_header.mapped.unwrap(),
+        (
+            "mapped.fasta".to_string(),
+            "fedcba9876543210fedcba9876543210".to_string()
+        )
+    );
+
+    // From before the header
+    let mapping = read("a\tFMMH_0\n").unwrap();
+    assert!(mapping.header.is_none());
+
+    assert_eq!(
+        read(&format!("{}a\tFMMH_0\nb\n", header)).unwrap_err(),
+        "line 6: expected old-header<TAB>new-header in: b"
+    );
+    assert_eq!(
+        read(&format!("{}a\tFMMH_0\nb\tFMMH_0\n", header)).unwrap_err(),
+        "line 6: new header FMMH_0 is already on line 5"
+    );
+    assert_eq!(
+        read("a\tFMMH_0\na\tFMMH_1\n").unwrap_err(),
+        "line 2: old header a is already on line 1"
+    );
+    assert_eq!(
+        read("#fasman-mapping\tversion=2\n").unwrap_err(),
+        "line 1: version 2 is newer than this FasMan reads (1)"
+    );
+    assert_eq!(
+        read("#fasman-mapping\tversion=1\na\tFMMH_0\n").unwrap_err(),
+        "line 2: the header has no #old-header<TAB>new-header column names"
+    );
+    assert_eq!(
+        read("#fasman-mapping\tversion=1\n#mapped\tmapped.fasta\tmd5=xyz\n").unwrap_err(),
+        "line 2: expected #source or #mapped<TAB>PATH<TAB>md5=CHECKSUM in: #mapped\tmapped.fasta\tmd5=xyz"
+    );
+}
+
+#[test]
+fn check_remap_checks_the_fasta() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa");
+    fs::write(&fasta_path, FASTA).unwrap();
+    let output = format!("{}/", dir.path().display());
+
+    // Bgzipped, the checksum is of what is inside
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("mapheaders")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("-o")
+        .arg(&output)
+        .arg("-z")
+        .arg("bgzip")
+        .assert()
+        .success();
+    let mapping = fs::read_to_string(dir.path().join("mapped-heads.tsv")).unwrap();
+    assert!(mapping.starts_with(&format!(
+        "#fasman-mapping\tversion=1\tfasman={}\n#source\t",
+        env!("CARGO_PKG_VERSION")
+    )));
+
+    let remap = |fasta: &Path| {
+        Command::cargo_bin("fasta_manipulation")
+            .unwrap()
+            .arg("remapheaders")
+            .arg("-f")
+            .arg(fasta)
+            .arg("-m")
+            .arg(dir.path().join("mapped-heads.tsv"))
+            .arg("-o")
+            .arg(dir.path().join("remapped"))
+            .arg("-u")
+            .arg("keep")
+            .assert()
+    };
+    remap(&dir.path().join("mapped.fasta.gz")).success();
+    assert_eq!(
+        fs::read_to_string(dir.path().join("remapped_OH.fasta")).unwrap(),
+        FASTA
+    );
+
+    // The original isn't the mapped fasta, even though every header would be kept
+    remap(&fasta_path).failure();
+}
@@ -0,0 +1,142 @@
+use assert_cmd::Command;
+use std::fs::{self, File};
+use std::io::Write;
+use tempfile::Builder;
+
+use fasta_manipulation::merge_haps_mod::{merge_fastas, new_scaffold_name};
+use fasta_manipulation::{md5_of, Compression};
+
+#[test]
+fn new_scaffold_name_prefix_and_suffix() {
+    assert_eq!(new_scaffold_name("SUPER_1", "HAP1", false), "HAP1_SUPER_1");
+    assert_eq!(new_scaffold_name("SUPER_1", "HAP1", true), "SUPER_1_HAP1");
+}
+
+#[test]
+fn merge_fastas_three_inputs() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let hap1 = dir.path().join("hap1.fa");
+    let hap2 = dir.path().join("hap2.fa");
+    let mito = dir.path().join("mito.fa");
+    let output = dir.path().join("merged.fa");
+
+    write!(
+        File::create(&hap1).unwrap(),
+        ">SUPER_1\nACGT\n>SUPER_2\nGG\n"
+    )
+    .unwrap();
+    write!(File::create(&hap2).unwrap(), ">SUPER_1 desc\nTTTT\n").unwrap();
+    write!(File::create(&mito).unwrap(), ">MT\nCCC\n").unwrap();
+
+    let inputs = vec![
+        (hap1.to_str().unwrap(), "H1"),
+        (hap2.to_str().unwrap(), "H2"),
+        (mito.to_str().unwrap(), "ORG"),
+    ];
+    let (mapping, header) =
+        merge_fastas(&inputs, false, output.to_str().unwrap(), Compression::None).unwrap();
+
+    assert_eq!(
+        mapping,
+        vec![
+            ("SUPER_1".to_string(), "H1_SUPER_1".to_string()),
+            ("SUPER_2".to_string(), "H1_SUPER_2".to_string()),
+            ("SUPER_1".to_string(), "H2_SUPER_1".to_string()),
+            ("MT".to_string(), "ORG_MT".to_string()),
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        ">H1_SUPER_1\nACGT\n>H1_SUPER_2\nGG\n>H2_SUPER_1 desc\nTTTT\n>ORG_MT\nCCC\n"
+    );
+
+    // The checksums taken while merging are those of the files
+    assert_eq!(header.sources.len(), 3);
+    assert_eq!(header.sources[1].1, md5_of(&hap2).unwrap());
+    assert_eq!(header.mapped.unwrap().1, md5_of(&output).unwrap());
+}
+
+#[test]
+fn merge_fastas_refuses_collisions() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let hap1 = dir.path().join("hap1.fa");
+    let output = dir.path().join("merged.fa");
+    write!(File::create(&hap1).unwrap(), ">SUPER_1\nACGT\n").unwrap();
+
+    // The same label for both files gives the same names
+    let inputs = vec![
+        (hap1.to_str().unwrap(), "HAP"),
+        (hap1.to_str().unwrap(), "HAP"),
+    ];
+    assert!(merge_fastas(&inputs, false, output.to_str().unwrap(), Compression::None).is_err());
+}
+
+#[test]
+fn check_mergehaps_command() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let prefix = dir.path().join("merged");
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("mergehaps")
+        .arg("-p")
+        .arg("test_data/synthetic/tiny.fa")
+        .arg("-s")
+        .arg("test_data/synthetic/tiny.fa")
+        .arg("-n")
+        .arg("PRI/HAP")
+        .arg("-x")
+        .arg("suffix")
+        .arg("-o")
+        .arg(&prefix)
+        .assert()
+        .success();
+
+    let mapping = fs::read_to_string(dir.path().join("merged.mapped-heads.tsv")).unwrap();
+    assert!(mapping.starts_with("#fasman-mapping\tversion=1"));
+    assert_eq!(
+        mapping.lines().find(|x| !x.starts_with('#')).unwrap(),
+        "SG1\tSG1_PRI"
+    );
+    assert!(mapping.contains("SG1\tSG1_HAP"));
+}
+
+#[test]
+fn check_remapheaders_refuses_a_mergehaps_mapping() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let hap1 = dir.path().join("h1.fa");
+    let hap2 = dir.path().join("h2.fa");
+    let prefix = dir.path().join("merged");
+    fs::write(&hap1, ">SUPER_1\nACGT\n>SUPER_2\nGG\n").unwrap();
+    fs::write(&hap2, ">SUPER_1\nTTTT\n").unwrap();
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("mergehaps")
+        .arg("-p")
+        .arg(&hap1)
+        .arg("-s")
+        .arg(&hap2)
+        .arg("-n")
+        .arg("PRI/HAP")
+        .arg("-o")
+        .arg(&prefix)
+        .assert()
+        .success();
+
+    // Both copies of SUPER_1 would go back to SUPER_1
+    let assert = Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("remapheaders")
+        .arg("-f")
+        .arg(dir.path().join("merged.fasta"))
+        .arg("-m")
+        .arg(dir.path().join("merged.mapped-heads.tsv"))
+        .arg("-o")
+        .arg(dir.path().join("back"))
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert!(stderr.contains("USE remapfile"));
+    assert!(!dir.path().join("back_OH.fasta").exists());
+}
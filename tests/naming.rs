@@ -0,0 +1,206 @@
+use assert_cmd::Command;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use tempfile::Builder;
+
+use fasta_manipulation::naming_mod::{
+    read_assignments, Assignment, ChromosomeType, NamingScheme, ScaffoldRole,
+};
+
+fn assigned_scheme() -> NamingScheme {
+    let mut assignments = HashMap::new();
+    assignments.insert(
+        "RL_3".to_string(),
+        Assignment {
+            chromosome: "X".to_string(),
+            chromosome_type: ChromosomeType::Chromosome,
+            location: None,
+        },
+    );
+    assignments.insert(
+        "RL_4".to_string(),
+        Assignment {
+            chromosome: "LG4".to_string(),
+            chromosome_type: ChromosomeType::LinkageGroup,
+            location: None,
+        },
+    );
+    assignments.insert(
+        "SCAFFOLD_20".to_string(),
+        Assignment {
+            chromosome: "MT".to_string(),
+            chromosome_type: ChromosomeType::CircularChromosome,
+            location: Some("Mitochondrion".to_string()),
+        },
+    );
+    NamingScheme {
+        assignments,
+        ..NamingScheme::default()
+    }
+}
+
+#[test]
+fn check_default_naming() {
+    let naming = NamingScheme::default();
+    assert_eq!(naming.rename("RL_1"), "SUPER_1");
+    assert_eq!(naming.rename("RL_12_unloc_2"), "SUPER_12_unloc_2");
+    // Only the prefix is replaced, not every RL in the name
+    assert_eq!(naming.rename("SCAFFOLD_CURL_4"), "SCAFFOLD_CURL_4");
+    assert_eq!(naming.rename("SUPER_1"), "SUPER_1");
+    assert_eq!(naming.role("SCAFFOLD_84"), ScaffoldRole::Unplaced);
+}
+
+#[test]
+fn check_assigned_naming() {
+    let naming = NamingScheme {
+        prefix: "chr".to_string(),
+        unloc_suffix: "random".to_string(),
+        unplaced_suffix: Some("unplaced".to_string()),
+        ..assigned_scheme()
+    };
+    assert_eq!(naming.rename("RL_1"), "chr_1");
+    assert_eq!(naming.rename("RL_3"), "chr_X");
+    assert_eq!(naming.rename("RL_3_unloc_1"), "chr_X_random_1");
+    assert_eq!(naming.rename("SCAFFOLD_20"), "chr_MT");
+    assert_eq!(naming.rename("SCAFFOLD_84"), "SCAFFOLD_84_unplaced");
+
+    // The renamed scaffolds keep their role
+    assert_eq!(
+        naming.role("chr_X_random_1"),
+        ScaffoldRole::Unlocalised {
+            chromosome: "X".to_string(),
+            index: "1".to_string()
+        }
+    );
+    assert_eq!(
+        naming.role("chr_MT"),
+        ScaffoldRole::Chromosome {
+            chromosome: "MT".to_string(),
+            chromosome_type: ChromosomeType::CircularChromosome,
+            location: Some("Mitochondrion".to_string())
+        }
+    );
+
+    // RL_4 -> X would clash with RL_3
+    let mut clash = assigned_scheme();
+    clash.assignments.insert(
+        "RL_4".to_string(),
+        Assignment {
+            chromosome: "X".to_string(),
+            chromosome_type: ChromosomeType::Chromosome,
+            location: None,
+        },
+    );
+    assert!(clash
+        .rename_all(&["RL_3".to_string(), "RL_4".to_string()])
+        .is_err());
+}
+
+#[test]
+fn check_chromosome_lists() {
+    let naming = assigned_scheme();
+    let names: Vec<String> = [
+        "SUPER_1",
+        "SUPER_X",
+        "SUPER_X_unloc_1",
+        "SUPER_LG4",
+        "SUPER_MT",
+        "SCAFFOLD_84",
+    ]
+    .iter()
+    .map(|x| x.to_string())
+    .collect();
+
+    let mut chromosomes: Vec<u8> = Vec::new();
+    let mut unlocalised: Vec<u8> = Vec::new();
+    naming
+        .write_chromosome_lists(&names, &mut chromosomes, &mut unlocalised)
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(chromosomes).unwrap(),
+        "SUPER_1\t1\tChromosome\nSUPER_X\tX\tChromosome\nSUPER_LG4\tLG4\tLinkage-Group\nSUPER_MT\tMT\tCircular-Chromosome\tMitochondrion\n"
+    );
+    assert_eq!(
+        String::from_utf8(unlocalised).unwrap(),
+        "SUPER_X_unloc_1\tX\n"
+    );
+}
+
+#[test]
+fn check_read_assignments() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let table = dir.path().join("chromosomes.tsv");
+
+    write!(
+        File::create(&table).unwrap(),
+        "# TPF\tCHROMOSOME\tTYPE\tLOCATION\nRL_3\tX\nRL_4\tLG4\tLinkage-Group\nSCAFFOLD_20\tMT\tCircular-Chromosome\tMitochondrion\n"
+    )
+    .unwrap();
+    assert_eq!(
+        read_assignments(table.to_str().unwrap()).unwrap(),
+        assigned_scheme().assignments
+    );
+
+    write!(File::create(&table).unwrap(), "RL_3\tX\nRL_3\tW\n").unwrap();
+    assert!(read_assignments(table.to_str().unwrap())
+        .unwrap_err()
+        .to_string()
+        .ends_with("line 2: RL_3 is assigned more than once"));
+
+    // The type comes before the location
+    writeln!(
+        File::create(&table).unwrap(),
+        "SCAFFOLD_20\tMT\tMitochondrion"
+    )
+    .unwrap();
+    assert!(read_assignments(table.to_str().unwrap())
+        .unwrap_err()
+        .to_string()
+        .ends_with(
+            "line 1: Mitochondrion is not Chromosome, Circular-Chromosome or Linkage-Group"
+        ));
+}
+
+#[test]
+fn check_curate_with_chromosome_map() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let table = dir.path().join("chromosomes.tsv");
+    let output = dir.path().join("curated.fa");
+    writeln!(File::create(&table).unwrap(), "RL_3\tZ").unwrap();
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .current_dir(dir.path())
+        .arg("curate")
+        .arg("-f")
+        .arg(fs::canonicalize("test_data/iyAndFlav1/small/small_test.fa").unwrap())
+        .arg("-t")
+        .arg(fs::canonicalize("test_data/iyAndFlav1/small/small_test.curated.tpf").unwrap())
+        .arg("-m")
+        .arg(&table)
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+
+    let headers: Vec<String> = fs::read_to_string(&output)
+        .unwrap()
+        .lines()
+        .filter(|x| x.starts_with('>'))
+        .map(|x| x.to_string())
+        .collect();
+    assert_eq!(
+        headers,
+        vec![">SUPER_1", ">SUPER_2", ">SUPER_Z_unloc_1", ">SCAFFOLD_3"]
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("curated.chromosome_list.tsv")).unwrap(),
+        "SUPER_1\t1\tChromosome\nSUPER_2\t2\tChromosome\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("curated.unlocalised_list.tsv")).unwrap(),
+        "SUPER_Z_unloc_1\tZ\n"
+    );
+}
@@ -0,0 +1,93 @@
+use assert_cmd::Command;
+use std::fs;
+use tempfile::Builder;
+
+use fasta_manipulation::profile_mod::{
+    json_string, nx_lx, profile_fasta, profile_to_dir, summarise, SequenceProfile,
+};
+
+#[test]
+fn sequence_profile_counts_bases_and_gaps() {
+    let profile = SequenceProfile::from_sequence("seq1", b"GCatNNNNacgtnnA");
+    assert_eq!(profile.length, 15);
+    assert_eq!(profile.gc_count, 4);
+    assert_eq!(profile.at_count, 5);
+    assert_eq!(profile.n_count, 6);
+    assert_eq!(profile.gap_count, 2);
+    assert_eq!(profile.gc_percent(), 4.0 / 9.0 * 100.0);
+}
+
+#[test]
+fn nx_lx_of_known_lengths() {
+    // Total 100, 50% is reached at the second sequence
+    let lengths = vec![10, 40, 20, 30];
+    assert_eq!(nx_lx(&lengths, 0.5), (30, 2));
+    assert_eq!(nx_lx(&lengths, 0.9), (20, 3));
+    assert_eq!(nx_lx(&[], 0.5), (0, 0));
+}
+
+#[test]
+fn profile_synthetic_fasta() {
+    let profiles = profile_fasta("test_data/synthetic/tiny.fa").unwrap();
+    let first = profiles.first().unwrap();
+    assert_eq!(first.name, "SG1");
+    assert_eq!(first.length, 59);
+    assert_eq!(first.n_count, 2);
+    assert_eq!(first.gap_count, 1);
+
+    let summary = summarise(&profiles);
+    assert_eq!(summary.sequence_count, profiles.len());
+    assert_eq!(
+        summary.total_length,
+        profiles.iter().map(|x| x.length).sum::<usize>()
+    );
+    assert_eq!(
+        summary.gap_count,
+        profiles.iter().map(|x| x.gap_count).sum::<usize>()
+    );
+    assert!(summary.longest.1 >= summary.shortest.1);
+}
+
+#[test]
+fn check_profile_command() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let outdir = dir.path().join("profile");
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("profile")
+        .arg("-f")
+        .arg("test_data/synthetic/tiny.fa")
+        .arg("-o")
+        .arg(&outdir)
+        .assert()
+        .success();
+
+    let tsv = fs::read_to_string(outdir.join("tiny.sequences.tsv")).unwrap();
+    assert!(tsv.starts_with("name\tlength\t"));
+    assert!(tsv.lines().nth(1).unwrap().starts_with("SG1\t59\t"));
+    assert!(outdir.join("tiny.summary.txt").exists());
+    assert!(fs::read_to_string(outdir.join("tiny.summary.json"))
+        .unwrap()
+        .contains("\"n50\""));
+}
+
+#[test]
+fn check_summary_json_is_escaped() {
+    assert_eq!(json_string("SG1"), "\"SG1\"");
+    assert_eq!(
+        json_string("a\"b\\c\td\u{1b}é"),
+        "\"a\\\"b\\\\c\\td\\u001bé\""
+    );
+
+    // Names are only split on whitespace, so can hold quotes and backslashes
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta = dir.path().join("quoted.fa");
+    fs::write(&fasta, ">say\"hi\\\nACGT\n>short\nAC\n").unwrap();
+    let outdir = dir.path().join("profile");
+    profile_to_dir(fasta.to_str().unwrap(), outdir.to_str().unwrap()).unwrap();
+
+    let json = fs::read_to_string(outdir.join("quoted.summary.json")).unwrap();
+    assert!(json.contains("\"longest\": {\"name\": \"say\\\"hi\\\\\", \"length\": 4},"));
+    assert!(json.contains("\"shortest\": {\"name\": \"short\", \"length\": 2}"));
+}
@@ -0,0 +1,246 @@
+use assert_cmd::Command;
+use noodles::sam::header::record::value::map::reference_sequence::tag;
+use noodles::sam::header::record::value::map::ReferenceSequence;
+use noodles::sam::header::record::value::Map;
+use noodles::{cram, sam};
+use std::fs;
+use std::num::NonZeroUsize;
+use tempfile::Builder;
+
+use fasta_manipulation::remap_file_mod::{detect_format, remap_lines, NameMap};
+
+const MAPPING: &str = "SCAFFOLD_1\tFMMH_0\nSCAFFOLD_2\tFMMH_1\n";
+
+fn back() -> NameMap {
+    NameMap::new(vec![
+        ("FMMH_0".to_string(), "SCAFFOLD_1".to_string()),
+        ("FMMH_1".to_string(), "SCAFFOLD_2".to_string()),
+    ])
+    .unwrap()
+}
+
+fn remap(input: &str, format: &str, names: &mut NameMap) -> String {
+    let mut out: Vec<u8> = Vec::new();
+    remap_lines(&mut input.as_bytes(), format, names, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn check_detect_format() {
+    assert_eq!(detect_format("genes.gff.gz").unwrap(), "gff3");
+    assert_eq!(detect_format("aln.PAF").unwrap(), "paf");
+    assert_eq!(detect_format("hic.cram").unwrap(), "cram");
+    assert!(detect_format("notes.txt").is_none());
+}
+
+#[test]
+fn check_remap_formats() {
+    let mut names = back();
+    assert_eq!(
+        remap(
+            "track name=x\nFMMH_0\t0\t10\tgene\nFMMH_9\t5\t6\n",
+            "bed",
+            &mut names
+        ),
+        "track name=x\nSCAFFOLD_1\t0\t10\tgene\nFMMH_9\t5\t6\n"
+    );
+    assert_eq!(names.renamed, 1);
+    assert_eq!(names.unmatched["FMMH_9"], 1);
+
+    assert_eq!(
+        remap(
+            "##gff-version 3\n##sequence-region FMMH_1 1 100\nFMMH_1\t.\tgene\t1\t9\t.\t+\t.\tID=FMMH_0\n##FASTA\n>FMMH_1 desc\nACGT\n",
+            "gff3",
+            &mut back()
+        ),
+        "##gff-version 3\n##sequence-region SCAFFOLD_2 1 100\nSCAFFOLD_2\t.\tgene\t1\t9\t.\t+\t.\tID=FMMH_0\n##FASTA\n>SCAFFOLD_2 desc\nACGT\n"
+    );
+    assert_eq!(
+        remap(
+            "FMMH_0\t100\t0\t10\t+\tFMMH_1\t200\t5\t15\t10\t10\t60\n",
+            "paf",
+            &mut back()
+        ),
+        "SCAFFOLD_1\t100\t0\t10\t+\tSCAFFOLD_2\t200\t5\t15\t10\t10\t60\n"
+    );
+    assert_eq!(
+        remap(
+            "SUPER_1\t1\t10\t1\tW\tFMMH_0\t1\t10\t+\nSUPER_1\t11\t20\t2\tU\t10\tscaffold\tyes\tproximity_ligation\n",
+            "agp",
+            &mut back()
+        ),
+        "SUPER_1\t1\t10\t1\tW\tSCAFFOLD_1\t1\t10\t+\nSUPER_1\t11\t20\t2\tU\t10\tscaffold\tyes\tproximity_ligation\n"
+    );
+    assert_eq!(
+        remap(
+            "?\tFMMH_1:1-9\tRL_1\tPLUS\nGAP\tTYPE-2\t200\n",
+            "tpf",
+            &mut back()
+        ),
+        "?\tSCAFFOLD_2:1-9\tRL_1\tPLUS\nGAP\tTYPE-2\t200\n"
+    );
+    assert_eq!(
+        remap(
+            "@SQ\tSN:FMMH_0\tLN:100\nread1\t0\tFMMH_0\t1\t60\t4M\tFMMH_1\t50\t0\tACGT\t*\nread2\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\t*\n",
+            "sam",
+            &mut back()
+        ),
+        "@SQ\tSN:SCAFFOLD_1\tLN:100\nread1\t0\tSCAFFOLD_1\t1\t60\t4M\tSCAFFOLD_2\t50\t0\tACGT\t*\nread2\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\t*\n"
+    );
+
+    let mut out: Vec<u8> = Vec::new();
+    let error = remap_lines(&mut "?\tFMMH_1\n".as_bytes(), "tpf", &mut back(), &mut out)
+        .unwrap_err()
+        .to_string();
+    assert!(error.starts_with("line 1:"));
+}
+
+#[test]
+fn check_remapfile_command() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let map_file = dir.path().join("mapped-heads.tsv");
+    let bed = dir.path().join("input.bed");
+    let output = dir.path().join("output.bed");
+    fs::write(&map_file, MAPPING).unwrap();
+    fs::write(&bed, "SCAFFOLD_1\t0\t10\nSCAFFOLD_3\t0\t10\n").unwrap();
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("remapfile")
+        .arg("-i")
+        .arg(&bed)
+        .arg("-m")
+        .arg(&map_file)
+        .arg("-o")
+        .arg(&output)
+        .arg("-d")
+        .arg("forward")
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        "FMMH_0\t0\t10\nSCAFFOLD_3\t0\t10\n"
+    );
+
+    // Only the header of a CRAM
+    let cram_path = dir.path().join("input.cram");
+    // With its M5 the CRAM writer doesn't need the reference
+    let mut reference_sequence = Map::<ReferenceSequence>::new(NonZeroUsize::new(100).unwrap());
+    reference_sequence
+        .other_fields_mut()
+        .insert(tag::MD5_CHECKSUM, "0123456789abcdef0123456789abcdef".into());
+    let header = sam::Header::builder()
+        .add_reference_sequence("FMMH_1", reference_sequence)
+        .build();
+    let mut writer = cram::io::Writer::new(fs::File::create(&cram_path).unwrap());
+    writer.write_header(&header).unwrap();
+    writer.try_finish(&header).unwrap();
+
+    let header_out = dir.path().join("header.sam");
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("remapfile")
+        .arg("-i")
+        .arg(&cram_path)
+        .arg("-m")
+        .arg(&map_file)
+        .arg("-o")
+        .arg(&header_out)
+        .assert()
+        .success();
+    assert!(fs::read_to_string(&header_out)
+        .unwrap()
+        .contains("@SQ\tSN:SCAFFOLD_2\tLN:100\t"));
+}
+
+#[test]
+fn check_remapfile_merged_map() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let map_file = dir.path().join("merged.mapped-heads.tsv");
+    let bed = dir.path().join("input.bed");
+    let output = dir.path().join("output.bed");
+    // As mergehaps writes it, SUPER_1 is in both haplotypes
+    fs::write(
+        &map_file,
+        "#fasman-mapping\tversion=1\tfasman=0.1.5\n\
+         #source\th1.fa\tmd5=0123456789abcdef0123456789abcdef\n\
+         #source\th2.fa\tmd5=fedcba9876543210fedcba9876543210\n\
+         #mapped\tmerged.fasta\tmd5=00112233445566778899aabbccddeeff\n\
+         #old-header\tnew-header\n\
+         SUPER_1\tPRI_SUPER_1\n\
+         SUPER_2\tPRI_SUPER_2\n\
+         SUPER_1\tHAP_SUPER_1\n",
+    )
+    .unwrap();
+    fs::write(&bed, "PRI_SUPER_1\t0\t10\nHAP_SUPER_1\t0\t10\n").unwrap();
+
+    let run = |direction: &str| {
+        Command::cargo_bin("fasta_manipulation")
+            .unwrap()
+            .arg("remapfile")
+            .arg("-i")
+            .arg(&bed)
+            .arg("-m")
+            .arg(&map_file)
+            .arg("-o")
+            .arg(&output)
+            .arg("-d")
+            .arg(direction)
+            .assert()
+    };
+    run("back").success();
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        "SUPER_1\t0\t10\nSUPER_1\t0\t10\n"
+    );
+
+    // Forward SUPER_1 could be either
+    let stderr = run("forward").failure().get_output().stderr.clone();
+    assert!(String::from_utf8(stderr)
+        .unwrap()
+        .contains("1 names map to more than one name: SUPER_1 (PRI_SUPER_1, HAP_SUPER_1)"));
+    assert_eq!(
+        NameMap::new(vec![
+            ("a".to_string(), "x".to_string()),
+            ("a".to_string(), "y".to_string())
+        ])
+        .unwrap_err(),
+        "1 names map to more than one name: a (x, y)"
+    );
+}
+
+#[test]
+fn check_remapfile_curated_tpf() {
+    // Tab and space separated lines, like curate reads
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let map_file = dir.path().join("mapped-heads.tsv");
+    let output = dir.path().join("output.tpf");
+    fs::write(
+        &map_file,
+        "SCAFFOLD_1\tFMMH_0\nSCAFFOLD_2\tFMMH_1\nSCAFFOLD_3\tFMMH_2\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("remapfile")
+        .arg("-i")
+        .arg("test_data/iyAndFlav1/small/small_test.curated.tpf")
+        .arg("-m")
+        .arg(&map_file)
+        .arg("-o")
+        .arg(&output)
+        .arg("-d")
+        .arg("forward")
+        .assert()
+        .success();
+
+    let original = fs::read_to_string("test_data/iyAndFlav1/small/small_test.curated.tpf").unwrap();
+    let expected = original
+        .replace("SCAFFOLD_1:", "FMMH_0:")
+        .replace("SCAFFOLD_2:", "FMMH_1:")
+        .replace("SCAFFOLD_3:", "FMMH_2:");
+    let renamed = fs::read_to_string(&output).unwrap();
+    assert_eq!(renamed, expected);
+    assert!(renamed.contains("?   FMMH_2:1-5  RL_2    PLUS\n"));
+}
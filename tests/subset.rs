@@ -0,0 +1,66 @@
+use assert_cmd::Command;
+use std::fs;
+use tempfile::Builder;
+
+use fasta_manipulation::subset_mod::{record_lengths, select_head, select_random};
+
+#[test]
+fn select_head_by_records_and_bases() {
+    let lengths = vec![10, 20, 30, 40];
+    assert_eq!(
+        select_head(&lengths, 50, false),
+        vec![true, true, false, false]
+    );
+    // 50% of 100 bases needs the first three records
+    assert_eq!(
+        select_head(&lengths, 50, true),
+        vec![true, true, true, false]
+    );
+    assert_eq!(select_head(&lengths, 0, false), vec![false; 4]);
+}
+
+#[test]
+fn select_random_is_reproducible_with_seed() {
+    let lengths: Vec<usize> = (1..=100).collect();
+
+    let first = select_random(&lengths, 25, false, 42);
+    let second = select_random(&lengths, 25, false, 42);
+    assert_eq!(first, second);
+    assert_eq!(first.iter().filter(|x| **x).count(), 25);
+    assert_ne!(first, select_random(&lengths, 25, false, 43));
+
+    let by_bases = select_random(&lengths, 25, true, 42);
+    let kept: usize = lengths
+        .iter()
+        .zip(&by_bases)
+        .filter(|x| *x.1)
+        .map(|x| x.0)
+        .sum();
+    assert!(kept * 4 >= lengths.iter().sum::<usize>());
+}
+
+#[test]
+fn check_subset_command() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let output = dir.path().join("subset.fa");
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("subset")
+        .arg("-f")
+        .arg("test_data/synthetic/tiny.fa")
+        .arg("-r")
+        .arg("true")
+        .arg("-s")
+        .arg("7")
+        .arg("-p")
+        .arg("40")
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+
+    let lengths = record_lengths(output.to_str().unwrap()).unwrap();
+    assert_eq!(lengths.len(), 2);
+    assert!(fs::read_to_string(&output).unwrap().starts_with('>'));
+}
@@ -1,39 +1,37 @@
 use assert_cmd::Command;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io::Write;
 
-use noodles::fasta::record::Sequence;
 use tempfile::Builder;
 
+use fasta_manipulation::naming_mod::NamingScheme;
 use fasta_manipulation::tpf_fasta_mod::{
-    check_orientation, get_uniques, parse_seq, parse_tpf, save_to_fasta, subset_vec_tpf, NewFasta,
-    Tpf,
+    check_layout, complement_base, components, gapped_tpf, group_by_scaffold, is_agp, natural_cmp,
+    orient, parse_tpf_entries, read_agp, read_tpf, reverse_complement, scaffold_order,
+    sequence_lengths, sort_entries, write_curated, ComponentSource, Coverage, Gap, IndexedSource,
+    Tpf, TpfEntry,
 };
 
+use fasta_manipulation::open_indexed_fasta;
+
 mod util;
 
 use util::are_files_identical;
 
-// To test the check orientation function we need to publicly expose it
-// Is there a way to test private functions?
 #[test]
 fn check_orientation_inverts_sequence_if_minus() {
-    let sequence = Sequence::from(b"ATGC".to_vec());
-    let orientation = "MINUS".to_string();
-    let result = check_orientation(Some(sequence), orientation);
-    assert_eq!(result, "GCAT".to_string());
+    assert_eq!(orient(b"ATGC", "MINUS").unwrap(), b"GCAT");
 }
 
 #[test]
 fn check_orientation_does_not_invert_sequence_if_plus() {
-    let sequence = Sequence::from(b"ATGC".to_vec());
-    let orientation = "PLUS".to_string();
-    let result = check_orientation(Some(sequence), orientation);
-    assert_eq!(result, "ATGC".to_string());
+    assert_eq!(orient(b"ATGC", "PLUS").unwrap(), b"ATGC");
 }
 
-// Again we need to publicly expose the get_uniques function to test it
 // Also we need to publicly expose the Tpf struct attributes
 // Do we need a factory function to create Tpf structs?
 #[test]
@@ -44,6 +42,7 @@ fn get_uniques_returns_unique_scaffold_names() {
         end_coord: 100,
         new_scaffold: "newScaffold1".to_string(),
         orientation: "PLUS".to_string(),
+        component_type: 'W',
     };
     let tpf2 = Tpf {
         ori_scaffold: "scaffold2".to_string(),
@@ -51,6 +50,7 @@ fn get_uniques_returns_unique_scaffold_names() {
         end_coord: 100,
         new_scaffold: "newScaffold2".to_string(),
         orientation: "PLUS".to_string(),
+        component_type: 'W',
     };
     let tpf3 = Tpf {
         ori_scaffold: "scaffold1".to_string(),
@@ -58,56 +58,45 @@ fn get_uniques_returns_unique_scaffold_names() {
         end_coord: 100,
         new_scaffold: "newScaffold1".to_string(),
         orientation: "PLUS".to_string(),
+        component_type: 'W',
     };
     let tpfs = vec![tpf1, tpf2, tpf3];
-    let result = get_uniques(&tpfs);
+    let result = scaffold_order(&gapped_tpf(tpfs, 200));
     assert_eq!(
         result,
         vec!["newScaffold1".to_string(), "newScaffold2".to_string()]
     );
 }
 
-// Need to add some docs for function
-// as we were not entirely sure what it was doing
-#[test]
-fn get_subset_of_tpfs() {
-    let tpf1 = Tpf {
-        ori_scaffold: "scaffold1".to_string(),
-        start_coord: 1,
-        end_coord: 100,
-        new_scaffold: "newScaffold1".to_string(),
-        orientation: "PLUS".to_string(),
-    };
-    let tpf2 = Tpf {
-        ori_scaffold: "scaffold2".to_string(),
-        start_coord: 1,
-        end_coord: 100,
-        new_scaffold: "newScaffold2".to_string(),
-        orientation: "PLUS".to_string(),
-    };
-    let tpf3 = Tpf {
-        ori_scaffold: "scaffold1".to_string(),
-        start_coord: 1,
-        end_coord: 100,
-        new_scaffold: "newScaffold1".to_string(),
-        orientation: "PLUS".to_string(),
-    };
-    let tpfs = vec![tpf1, tpf2, tpf3];
-    let fasta = (&"scaffold1".to_string(), &(1 as usize));
-    let result = subset_vec_tpf(&tpfs, fasta);
-    assert_eq!(result.len(), 2);
+fn indexed_fasta(
+    dir: &std::path::Path,
+    fasta: &str,
+) -> (
+    noodles::fasta::io::IndexedReader<noodles::fasta::io::BufReader<File>>,
+    HashMap<String, usize>,
+) {
+    let fasta_path = dir.join("input.fa");
+    fs::write(&fasta_path, fasta).unwrap();
+    let lengths = sequence_lengths(fasta_path.to_str().unwrap()).unwrap();
+    (open_indexed_fasta(&fasta_path).unwrap(), lengths)
 }
 
 #[test]
 fn check_parse_seq() {
-    let sequence =
-        Sequence::from(b"AATGGCCGGCGCGTTAAACCCAATGCCCCGGTTAANNGCTCGTCGCTTGCTTCGCAAAA".to_vec());
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let sequence = "AATGGCCGGCGCGTTAAACCCAATGCCCCGGTTAANNGCTCGTCGCTTGCTTCGCAAAA";
+    let (reader, lengths) = indexed_fasta(
+        dir.path(),
+        &format!(">scaffold1\n{}\n>scaffold2\n{}\n", sequence, sequence),
+    );
+    let mut source = IndexedSource::new(reader, &lengths);
     let tpf1 = Tpf {
         ori_scaffold: "scaffold1".to_string(),
         start_coord: 3,
         end_coord: 5,
         new_scaffold: "newScaffold1".to_string(),
         orientation: "PLUS".to_string(),
+        component_type: 'W',
     };
     let tpf2 = Tpf {
         ori_scaffold: "scaffold2".to_string(),
@@ -115,6 +104,7 @@ fn check_parse_seq() {
         end_coord: 20,
         new_scaffold: "newScaffold2".to_string(),
         orientation: "MINUS".to_string(),
+        component_type: 'W',
     };
     let tpf3 = Tpf {
         ori_scaffold: "scaffold1".to_string(),
@@ -122,47 +112,62 @@ fn check_parse_seq() {
         end_coord: 58,
         new_scaffold: "newScaffold1".to_string(),
         orientation: "PLUS".to_string(),
+        component_type: 'W',
     };
+    let entries: Vec<TpfEntry> = vec![tpf1, tpf2, tpf3]
+        .into_iter()
+        .map(TpfEntry::Component)
+        .collect();
 
-    let tpfs = vec![&tpf1, &tpf2, &tpf3];
-    let input_sequence = Some(sequence);
-
-    let new_fasta = parse_seq(input_sequence, tpfs);
+    let mut fasta_out: Vec<u8> = Vec::new();
+    write_curated(&mut source, &entries, &mut fasta_out, None, None, 200).unwrap();
 
-    assert_eq!(new_fasta.len(), 3);
-    assert_eq!(new_fasta.first().unwrap().sequence, "TGG");
-    assert_eq!(new_fasta.get(1).unwrap().sequence, "GGTTTAACGCG");
     assert_eq!(
-        new_fasta.get(2).unwrap().sequence,
-        "AATGGCCGGCGCGTTAAACCCAATGCCCCGGTTAANNGCTCGTCGCTTGCTTCGCAAA"
+        String::from_utf8(fasta_out).unwrap(),
+        ">newScaffold1\n\
+         TGGAATGGCCGGCGCGTTAAACCCAATGCCCCGGTTAANNGCTCGTCGCTTGCTTCGCAA\n\
+         A\n\
+         >newScaffold2\n\
+         GGTTTAACGCG\n"
     );
 }
 
-// This should panic with a end_coord > sequence.length
-// Should the exception be handled in a more graceful way?
+// An end_coord > sequence.length stops the curation
 #[test]
-#[should_panic]
 fn check_parse_seq_bounds_error() {
-    let sequence =
-        Sequence::from(b"AATGGCCGGCGCGTTAAACCCAATGCCCCGGTTAANNGCTCGTCGCTTGCTTCGCAAAA".to_vec());
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let (reader, lengths) = indexed_fasta(
+        dir.path(),
+        ">scaffold1\nAATGGCCGGCGCGTTAAACCCAATGCCCCGGTTAANNGCTCGTCGCTTGCTTCGCAAAA\n",
+    );
+    let mut source = IndexedSource::new(reader, &lengths);
     let tpf = Tpf {
         ori_scaffold: "scaffold1".to_string(),
         start_coord: 10,
         end_coord: 60,
         new_scaffold: "newScaffold1".to_string(),
         orientation: "PLUS".to_string(),
+        component_type: 'W',
     };
-    let tpfs = vec![&tpf];
 
-    let input_sequence = Some(sequence);
-
-    parse_seq(input_sequence, tpfs);
+    let error = write_curated(
+        &mut source,
+        &[TpfEntry::Component(tpf)],
+        &mut std::io::sink(),
+        None,
+        None,
+        200,
+    )
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("scaffold1:10-60 runs past the end of scaffold1 (length 59)"));
 }
 
 #[test]
 fn check_parse_tpf() {
     let path = "test_data/iyAndFlav1/full/iyAndFlav1.curated_subset.tpf".to_string();
-    let tpfs = parse_tpf(&path);
+    let tpfs = components(&parse_tpf_entries(&path));
     assert_eq!(tpfs.len(), 4);
 
     // ?	SCAFFOLD_12:1-900734	RL_3	MINUS
@@ -188,52 +193,10 @@ fn check_parse_tpf() {
 
 #[test]
 fn check_save_to_fasta() {
-    // Inputs: Vector of NewFasta types, vector of Tpf types, output path, and n_length
-    // 1. Creates a data file based on the output path, and open the created file using OpenOption
-    // 2. Creates a debug.txt file, and open that file.
-    // 3. Retrieving unique scaffolds based on the initial tpf types
-
-    // Iterating over the unique scaffold names:
-    // - appends a > symbol to the start and a new line to the end
-    // - appends the scaffold name to the file
-    // - appends the scaffold name to file2 ()debug.txt)
-    // - creates a struct called MyRecord with an empty name and sequence
-    // - assigns the unique scaffold name to data name
-    // - iterating over the tpf data (comes from parse_tpf function)
-    // - if the new scaffold name is equal to the unique scaffold name
-    // - iterates over the new_fasta data
-    // - checking for object equality
-    // - if the object is equal it formats the tpf into a string and writes it to file2 (debug.txt)
-    // - if the object is equal it appends the fasta sequence to the data sequence
-    // - creates a variable line_len set to 60
-    // - creates a fixed variable which is is the sequence
-    // - creates a n_string variable which is N repeated n_length times
-    // - creates fixed2 variable which is fixed joined with n_string
-    // - creates a variable called fixed3 which is converted to bytes and chunks it by line_len and converts it to a vector of strings
-    // - iterates over the fixed3 variable and writes it to the file
-
-    let new_fasta_items = vec![
-        NewFasta {
-            tpf: Tpf {
-                ori_scaffold: "SCAFFOLD_1".to_string(),
-                start_coord: 1,
-                end_coord: 9,
-                new_scaffold: "SUPER_1".to_string(),
-                orientation: "MINUS".to_string(),
-            },
-            sequence: "GGCATGCAT".to_string(),
-        },
-        NewFasta {
-            tpf: Tpf {
-                ori_scaffold: "SCAFFOLD_3".to_string(),
-                start_coord: 1,
-                end_coord: 5,
-                new_scaffold: "SUPER_2".to_string(),
-                orientation: "PLUS".to_string(),
-            },
-            sequence: "AGTGT".to_string(),
-        },
-    ];
+    // The fasta and its debug trace, every component joined by n_length N's
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let output = dir.path().join("new.fasta");
+    let debug = dir.path().join("new.debug.tsv");
 
     let tpf_items = vec![
         Tpf {
@@ -242,6 +205,7 @@ fn check_save_to_fasta() {
             end_coord: 9,
             new_scaffold: "SUPER_1".to_string(),
             orientation: "MINUS".to_string(),
+            component_type: 'W',
         },
         Tpf {
             ori_scaffold: "SCAFFOLD_3".to_string(),
@@ -249,31 +213,35 @@ fn check_save_to_fasta() {
             end_coord: 5,
             new_scaffold: "SUPER_2".to_string(),
             orientation: "PLUS".to_string(),
+            component_type: 'W',
         },
     ];
 
-    let output = &"new.fasta".to_string();
-
     let n_length: usize = 200;
+    let reader = open_indexed_fasta("test_data/iyAndFlav1/tiny/tiny_test.fa").unwrap();
+    let lengths = sequence_lengths("test_data/iyAndFlav1/tiny/tiny_test.fa").unwrap();
+    let mut fasta_out = File::create(&output).unwrap();
+    let mut debug_out = File::create(&debug).unwrap();
+    write_curated(
+        &mut IndexedSource::new(reader, &lengths),
+        &gapped_tpf(tpf_items, n_length),
+        &mut fasta_out,
+        Some(&mut debug_out),
+        None,
+        n_length,
+    )
+    .unwrap();
 
-    save_to_fasta(new_fasta_items, tpf_items, output, n_length);
-
-    assert!(
-        are_files_identical(output, "test_data/iyAndFlav1/tiny/tiny_test.output.fasta").unwrap()
-    );
-
-    assert!(
-        are_files_identical("debug.txt", "test_data/iyAndFlav1/tiny/tiny_test.debug.txt").unwrap()
-    );
-
-    match fs::remove_file(output) {
-        Ok(_) => true,
-        Err(_err) => panic!("File cannot be found!"),
-    };
-    match fs::remove_file("debug.txt") {
-        Ok(_) => true,
-        Err(_err) => panic!("File cannot be found!"),
-    };
+    assert!(are_files_identical(
+        output.to_str().unwrap(),
+        "test_data/iyAndFlav1/tiny/tiny_test.output.fasta"
+    )
+    .unwrap());
+    assert!(are_files_identical(
+        debug.to_str().unwrap(),
+        "test_data/iyAndFlav1/tiny/tiny_test.debug.tsv"
+    )
+    .unwrap());
 }
 
 //#[ignore = "Work in Progress (WIP)"]
@@ -294,7 +262,7 @@ fn check_curate_fasta() {
     let mut fai = File::create(fai_path).unwrap();
     let mut tpf = File::create(tpf_path).unwrap();
 
-    let output = "./output.fa";
+    let output = dir.path().join("output.fa");
 
     write!(
         fai,
@@ -320,7 +288,622 @@ fn check_curate_fasta() {
         .arg("-t")
         .arg(tpf_path)
         .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+
+    // Everything curate writes sits next to the output
+    assert!(output.exists());
+    assert!(dir.path().join("output.agp").exists());
+    assert!(dir.path().join("output.debug.tsv").exists());
+    assert!(dir.path().join("output.curation_report.tsv").exists());
+}
+
+#[test]
+fn check_is_agp_and_read_agp() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let agp_path = dir.path().join("input.layout");
+    let mut agp = File::create(&agp_path).unwrap();
+    write!(
+        agp,
+        "##agp-version\t2.1\n\
+         SUPER_1\t1\t9\t1\tW\tSCAFFOLD_1\t1\t9\t-\n\
+         SUPER_1\t10\t14\t2\tN\t5\tscaffold\tyes\tproximity_ligation\n\
+         SUPER_1\t15\t19\t3\tW\tSCAFFOLD_3\t1\t5\t+\n\
+         SUPER_2\t1\t100\t1\tU\t100\tcontig\tno\tna\n"
+    )
+    .unwrap();
+
+    let agp_path = agp_path.to_str().unwrap().to_string();
+    assert!(is_agp(&agp_path));
+    assert!(!is_agp(
+        &"test_data/iyAndFlav1/tiny/tiny_test.curated.tpf".to_string()
+    ));
+
+    let entries: Vec<TpfEntry> = read_agp(&agp_path)
+        .unwrap()
+        .into_iter()
+        .map(|x| x.1)
+        .collect();
+    assert_eq!(entries.len(), 4);
+    assert_eq!(
+        entries[0],
+        TpfEntry::Component(Tpf {
+            ori_scaffold: "SCAFFOLD_1".to_string(),
+            start_coord: 1,
+            end_coord: 9,
+            new_scaffold: "SUPER_1".to_string(),
+            orientation: "MINUS".to_string(),
+            component_type: 'W',
+        })
+    );
+    assert_eq!(
+        entries[1],
+        TpfEntry::Gap(Gap {
+            new_scaffold: "SUPER_1".to_string(),
+            component_type: 'N',
+            length: Some(5),
+            gap_type: "scaffold".to_string(),
+            linkage_evidence: "proximity_ligation".to_string(),
+        })
+    );
+    assert_eq!(components(&entries).len(), 2);
+    assert_eq!(entries[3].new_scaffold(), "SUPER_2");
+}
+
+#[test]
+fn check_gapped_tpf_groups_by_scaffold() {
+    let path = "test_data/iyAndFlav1/full/iyAndFlav1.curated_subset.tpf".to_string();
+    let entries = gapped_tpf(components(&parse_tpf_entries(&path)), 100);
+
+    // SUPER_3 has two components and so one gap between them
+    let scaffolds: Vec<&String> = entries.iter().map(|x| x.new_scaffold()).collect();
+    assert_eq!(
+        scaffolds,
+        vec![
+            "SUPER_3",
+            "SUPER_3",
+            "SUPER_3",
+            "SUPER_3_unloc_1",
+            "SCAFFOLD_84"
+        ]
+    );
+    assert_eq!(entries[1], TpfEntry::Gap(Gap::default_gap("SUPER_3", 100)));
+}
+
+#[test]
+fn check_curate_fasta_from_agp() {
+    let mut cmd = Command::cargo_bin("fasta_manipulation").unwrap();
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+
+    let fasta_path = &dir.path().join("input_fasta.fa");
+    let fai_path = &dir.path().join("input_fasta.fa.fai");
+    let agp_path = &dir.path().join("input.agp");
+    let output = &dir.path().join("output.fa");
+
+    write!(
+        File::create(fai_path).unwrap(),
+        "SCAFFOLD_1\t16\t12\t16\t17\nSCAFFOLD_3\t16\t41\t16\t17"
+    )
+    .unwrap();
+    write!(
+        File::create(fasta_path).unwrap(),
+        ">SCAFFOLD_1\nATGCATGCCGTATAGA\n>SCAFFOLD_3\nAGTGTATTTTTATGCA"
+    )
+    .unwrap();
+    write!(
+        File::create(agp_path).unwrap(),
+        "SUPER_1\t1\t9\t1\tW\tSCAFFOLD_1\t1\t9\t-\n\
+         SUPER_1\t10\t14\t2\tN\t5\tscaffold\tyes\tproximity_ligation\n\
+         SUPER_1\t15\t19\t3\tW\tSCAFFOLD_3\t1\t5\t+\n"
+    )
+    .unwrap();
+
+    cmd.current_dir(dir.path())
+        .arg("curate")
+        .arg("-f")
+        .arg(fasta_path)
+        .arg("-t")
+        .arg(agp_path)
+        .arg("-o")
         .arg(output)
         .assert()
         .success();
+
+    assert_eq!(
+        fs::read_to_string(output).unwrap(),
+        ">SUPER_1\nGGCATGCATNNNNNAGTGT\n"
+    );
+
+    // The AGP describes the same layout, keeping the AGP gap
+    let agp = fs::read_to_string(dir.path().join("output.agp")).unwrap();
+    assert!(agp.contains("SUPER_1\t10\t14\t2\tN\t5\tscaffold\tyes\tproximity_ligation\n"));
+    assert!(agp.contains("SUPER_1\t15\t19\t3\tW\tSCAFFOLD_3\t1\t5\t+\n"));
+}
+
+#[test]
+fn check_save_to_agp() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let output = dir.path().join("new.agp").to_str().unwrap().to_string();
+
+    let tpf1 = Tpf {
+        ori_scaffold: "SCAFFOLD_1".to_string(),
+        start_coord: 1,
+        end_coord: 9,
+        new_scaffold: "SUPER_1".to_string(),
+        orientation: "MINUS".to_string(),
+        component_type: 'W',
+    };
+    let tpf2 = Tpf {
+        ori_scaffold: "SCAFFOLD_3".to_string(),
+        start_coord: 3,
+        end_coord: 7,
+        new_scaffold: "SUPER_1".to_string(),
+        orientation: "PLUS".to_string(),
+        component_type: 'W',
+    };
+    let entries = gapped_tpf(vec![tpf1, tpf2], 200);
+
+    let reader = open_indexed_fasta("test_data/iyAndFlav1/tiny/tiny_test.fa").unwrap();
+    let lengths = sequence_lengths("test_data/iyAndFlav1/tiny/tiny_test.fa").unwrap();
+    let mut agp_out = File::create(&output).unwrap();
+    write_curated(
+        &mut IndexedSource::new(reader, &lengths),
+        &entries,
+        &mut std::io::sink(),
+        None,
+        Some(&mut agp_out),
+        200,
+    )
+    .unwrap();
+
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        "##agp-version\t2.1\n\
+         # AGP created by FasMan curate\n\
+         SUPER_1\t1\t9\t1\tW\tSCAFFOLD_1\t1\t9\t-\n\
+         SUPER_1\t10\t209\t2\tN\t200\tscaffold\tyes\tproximity_ligation\n\
+         SUPER_1\t210\t214\t3\tW\tSCAFFOLD_3\t3\t7\t+\n"
+    );
+}
+
+#[test]
+fn check_parse_tpf_entries_keeps_gaps() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let tpf_path = dir.path().join("input.tpf");
+    write!(
+        File::create(&tpf_path).unwrap(),
+        "GAP\tTYPE-2\t200\n\
+         ?\tSCAFFOLD_1:1-9\tRL_1\tMINUS\n\
+         GAP\tTYPE-2\t500\n\
+         ?\tSCAFFOLD_2:1-11\tRL_1\tPLUS\n\
+         GAP\tTYPE-3\n\
+         ?\tSCAFFOLD_2:12-20\tRL_1\tPLUS\n\
+         GAP\tTYPE-2\t200\n\
+         ?\tSCAFFOLD_3:1-5\tRL_2\tPLUS\n\
+         ?\tSCAFFOLD_3:6-10\tRL_1\tPLUS\n"
+    )
+    .unwrap();
+
+    let entries = parse_tpf_entries(&tpf_path.to_str().unwrap().to_string());
+    let gaps: Vec<&Gap> = entries
+        .iter()
+        .filter_map(|x| match x {
+            TpfEntry::Gap(gap) => Some(gap),
+            TpfEntry::Component(_) => None,
+        })
+        .collect();
+
+    // Leading and between scaffold gaps are dropped, SUPER_1
+    // restarting after SUPER_2 gets a gap of unknown size
+    assert_eq!(entries.len(), 8);
+    assert_eq!(gaps.len(), 3);
+    assert_eq!(gaps[0].length, Some(500));
+    assert_eq!(gaps[0].component_type, 'N');
+    assert_eq!(gaps[1].length, None);
+    assert_eq!(gaps[1].gap_type, "contig");
+    assert_eq!(gaps[1].linkage_evidence, "na");
+    assert_eq!(gaps[2].new_scaffold, "SUPER_1");
+    assert_eq!(gaps[2].component_type, 'U');
+    assert_eq!(gaps[2].resolved_length(100), 100);
+}
+
+#[test]
+fn check_curate_fasta_honours_tpf_gaps() {
+    let mut cmd = Command::cargo_bin("fasta_manipulation").unwrap();
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let root = std::env::current_dir().unwrap();
+    let output = &dir.path().join("output.fa");
+
+    // The n_length is ignored as the TPF gap has a size of 200
+    cmd.current_dir(dir.path())
+        .arg("curate")
+        .arg("-f")
+        .arg(root.join("test_data/iyAndFlav1/small/small_test.fa"))
+        .arg("-t")
+        .arg(root.join("test_data/iyAndFlav1/small/small_test.curated.tpf"))
+        .arg("-o")
+        .arg(output)
+        .arg("50")
+        .assert()
+        .success();
+
+    assert!(are_files_identical(
+        output.to_str().unwrap(),
+        "test_data/iyAndFlav1/small/small_test.output.fasta"
+    )
+    .unwrap());
+}
+
+#[test]
+fn check_indexed_source_fetches_components() {
+    let reader = noodles::fasta::io::indexed_reader::Builder::default()
+        .build_from_path("test_data/iyAndFlav1/tiny/tiny_test.fa")
+        .unwrap();
+    let lengths = sequence_lengths("test_data/iyAndFlav1/tiny/tiny_test.fa").unwrap();
+    let mut source = IndexedSource::new(reader, &lengths);
+
+    let tpf = Tpf {
+        ori_scaffold: "SCAFFOLD_1".to_string(),
+        start_coord: 1,
+        end_coord: 9,
+        new_scaffold: "SUPER_1".to_string(),
+        orientation: "MINUS".to_string(),
+        component_type: 'W',
+    };
+    assert_eq!(source.fetch(&tpf).unwrap(), b"GGCATGCAT");
+
+    let missing = Tpf {
+        ori_scaffold: "SCAFFOLD_404".to_string(),
+        ..tpf
+    };
+    assert!(source.fetch(&missing).is_err());
+}
+
+#[test]
+fn check_write_curated_streams_in_scaffold_order() {
+    let reader = noodles::fasta::io::indexed_reader::Builder::default()
+        .build_from_path("test_data/iyAndFlav1/small/small_test.fa")
+        .unwrap();
+    let lengths = sequence_lengths("test_data/iyAndFlav1/small/small_test.fa").unwrap();
+    let entries =
+        parse_tpf_entries(&"test_data/iyAndFlav1/small/small_test.curated.tpf".to_string());
+    let groups = group_by_scaffold(&entries);
+    assert_eq!(groups[0].0, "SUPER_1");
+    assert_eq!(groups[0].1.len(), 3);
+
+    let mut fasta_out: Vec<u8> = Vec::new();
+    let mut debug_out: Vec<u8> = Vec::new();
+    let mut agp_out: Vec<u8> = Vec::new();
+    write_curated(
+        &mut IndexedSource::new(reader, &lengths),
+        &entries,
+        &mut fasta_out,
+        Some(&mut debug_out),
+        Some(&mut agp_out),
+        100,
+    )
+    .unwrap();
+
+    assert_eq!(
+        String::from_utf8(fasta_out).unwrap(),
+        fs::read_to_string("test_data/iyAndFlav1/small/small_test.output.fasta").unwrap()
+    );
+    let agp = String::from_utf8(agp_out).unwrap();
+    assert_eq!(agp.lines().count(), 2 + 6);
+    assert!(agp.contains("SUPER_1\t210\t220\t3\tW\tSCAFFOLD_2\t1\t11\t+"));
+
+    // The debug trace has the same output coordinates as the AGP
+    let debug = String::from_utf8(debug_out).unwrap();
+    assert_eq!(debug.lines().count(), 1 + 5);
+    assert!(debug.contains("SUPER_1\t210\t220\t3\tSCAFFOLD_2\t1\t11\tPLUS\n"));
+}
+
+#[test]
+fn check_natural_cmp() {
+    let mut names = vec![
+        "SUPER_10",
+        "SUPER_2",
+        "SUPER_1_unloc_1",
+        "SUPER_1",
+        "SCAFFOLD_3",
+    ];
+    names.sort_by(|a, b| natural_cmp(a, b));
+    assert_eq!(
+        names,
+        vec![
+            "SCAFFOLD_3",
+            "SUPER_1",
+            "SUPER_1_unloc_1",
+            "SUPER_2",
+            "SUPER_10"
+        ]
+    );
+}
+
+#[test]
+fn check_sort_entries() {
+    // SUPER_1 = 9 + 200 + 11, SUPER_2 = 5, SUPER_3_unloc_1 = 9, SCAFFOLD_3 = 5
+    let entries =
+        parse_tpf_entries(&"test_data/iyAndFlav1/small/small_test.curated.tpf".to_string());
+    let naming = NamingScheme::default();
+
+    let unsorted = sort_entries(entries.clone(), "none", false, &naming, 100);
+    assert_eq!(unsorted, entries);
+
+    assert_eq!(
+        scaffold_order(&sort_entries(entries.clone(), "size", false, &naming, 100)),
+        vec!["SUPER_1", "SUPER_3_unloc_1", "SUPER_2", "SCAFFOLD_3"]
+    );
+    assert_eq!(
+        scaffold_order(&sort_entries(entries.clone(), "name", false, &naming, 100)),
+        vec!["SCAFFOLD_3", "SUPER_1", "SUPER_2", "SUPER_3_unloc_1"]
+    );
+    assert_eq!(
+        scaffold_order(&sort_entries(entries.clone(), "size", true, &naming, 100)),
+        vec!["SUPER_1", "SUPER_2", "SUPER_3_unloc_1", "SCAFFOLD_3"]
+    );
+
+    // The gap stays inside SUPER_1 when it is moved
+    let by_name = sort_entries(entries, "name", true, &naming, 100);
+    assert!(matches!(by_name[1], TpfEntry::Gap(_)));
+}
+
+#[test]
+fn check_read_tpf_reports_line_numbers() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let tpf = dir.path().join("bad.tpf");
+    let tpf_path = tpf.to_str().unwrap().to_string();
+
+    write!(
+        File::create(&tpf).unwrap(),
+        "?\tSCAFFOLD_1:1-9\tRL_1\tPLUS\nGAP\tTYPE-2\t200\n?\tSCAFFOLD_2:1-1O\tRL_1\tPLUS\n"
+    )
+    .unwrap();
+    let error = read_tpf(&tpf_path).unwrap_err().to_string();
+    assert!(error.starts_with("line 3: '1O' is not a valid coordinate"));
+
+    writeln!(File::create(&tpf).unwrap(), "?\tSCAFFOLD_1:9-1\tRL_1\tPLUS").unwrap();
+    assert!(read_tpf(&tpf_path)
+        .unwrap_err()
+        .to_string()
+        .starts_with("line 1: 9-1 is not a valid range"));
+
+    writeln!(File::create(&tpf).unwrap(), "?\tSCAFFOLD_1:1-9\tRL_1\tUP").unwrap();
+    assert!(read_tpf(&tpf_path)
+        .unwrap_err()
+        .to_string()
+        .starts_with("line 1: orientation should be PLUS or MINUS"));
+
+    let agp = dir.path().join("bad.agp");
+    write!(
+        File::create(&agp).unwrap(),
+        "##agp-version\t2.1\nSUPER_1\t1\t9\t1\tX\tSCAFFOLD_1\t1\t9\t+\n"
+    )
+    .unwrap();
+    assert!(read_agp(&agp.to_str().unwrap().to_string())
+        .unwrap_err()
+        .to_string()
+        .starts_with(
+            "line 2: unsupported component type 'X', expected one of A, D, F, G, O, P, W, N or U"
+        ));
+
+    // Finished and draft components, as in an assembly from a clone based project
+    let typed = dir.path().join("typed.txt");
+    write!(
+        File::create(&typed).unwrap(),
+        "SUPER_1\t1\t9\t1\tF\tSCAFFOLD_1\t1\t9\t-\n\
+         SUPER_1\t10\t209\t2\tN\t200\tscaffold\tyes\tproximity_ligation\n\
+         SUPER_1\t210\t214\t3\tD\tSCAFFOLD_3\t3\t7\t+\n"
+    )
+    .unwrap();
+    let typed = typed.to_str().unwrap().to_string();
+    assert!(is_agp(&typed));
+    let entries: Vec<TpfEntry> = read_agp(&typed).unwrap().into_iter().map(|x| x.1).collect();
+    assert_eq!(components(&entries).len(), 2);
+    assert_eq!(components(&entries)[0].orientation, "MINUS");
+    assert_eq!(components(&entries)[1].ori_scaffold, "SCAFFOLD_3");
+    assert_eq!(components(&entries)[0].component_type, 'F');
+
+    // The component types are written back out
+    let reader = open_indexed_fasta("test_data/iyAndFlav1/tiny/tiny_test.fa").unwrap();
+    let lengths = sequence_lengths("test_data/iyAndFlav1/tiny/tiny_test.fa").unwrap();
+    let mut agp_out: Vec<u8> = Vec::new();
+    write_curated(
+        &mut IndexedSource::new(reader, &lengths),
+        &entries,
+        &mut std::io::sink(),
+        None,
+        Some(&mut agp_out),
+        200,
+    )
+    .unwrap();
+    let agp_out = String::from_utf8(agp_out).unwrap();
+    assert!(agp_out.contains("SUPER_1\t1\t9\t1\tF\tSCAFFOLD_1\t1\t9\t-\n"));
+    assert!(agp_out.contains("SUPER_1\t210\t214\t3\tD\tSCAFFOLD_3\t3\t7\t+\n"));
+}
+
+#[test]
+fn check_layout_against_fasta_lengths() {
+    let lengths = sequence_lengths("test_data/iyAndFlav1/small/small_test.fa").unwrap();
+    assert_eq!(lengths.get("SCAFFOLD_1"), Some(&74));
+
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let tpf = dir.path().join("check.tpf");
+    write!(
+        File::create(&tpf).unwrap(),
+        "?\tSCAFFOLD_1:1-50\tRL_1\tPLUS\n\
+         ?\tSCAFFOLD_1:41-74\tRL_1\tMINUS\n\
+         ?\tSCAFFOLD_2:1-74\tRL_2\tPLUS\n\
+         ?\tSCAFFOLD_3:1-10\tRL_3\tPLUS\n\
+         ?\tSCAFFOLD_3:20-90\tRL_3\tPLUS\n\
+         ?\tSCAFFOLD_9:1-10\tRL_4\tPLUS\n"
+    )
+    .unwrap();
+
+    let report = check_layout(
+        &read_tpf(&tpf.to_str().unwrap().to_string()).unwrap(),
+        &lengths,
+    );
+    assert_eq!(
+        report.errors,
+        vec![
+            "line 5: SCAFFOLD_3:20-90 runs past the end of SCAFFOLD_3 (length 85)".to_string(),
+            "line 6: SCAFFOLD_9 is not in the fasta".to_string(),
+        ]
+    );
+    assert_eq!(
+        report.duplicated,
+        vec![Coverage {
+            scaffold: "SCAFFOLD_1".to_string(),
+            start: 41,
+            end: 50,
+            lines: vec![1, 2],
+        }]
+    );
+    assert_eq!(
+        report.uncovered,
+        vec![Coverage {
+            scaffold: "SCAFFOLD_3".to_string(),
+            start: 11,
+            end: 85,
+            lines: Vec::new(),
+        }]
+    );
+    assert!(!report.is_valid());
+}
+
+#[test]
+fn check_validatetpf_command() {
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("validatetpf")
+        .arg("-f")
+        .arg("test_data/iyAndFlav1/full/iyAndFlav1_subset.fa")
+        .arg("-t")
+        .arg("test_data/iyAndFlav1/full/iyAndFlav1.curated_subset.tpf")
+        .assert()
+        .success();
+
+    // The small test TPF leaves the ends of its scaffolds unused
+    let output = Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("validatetpf")
+        .arg("-f")
+        .arg("test_data/iyAndFlav1/small/small_test.fa")
+        .arg("-t")
+        .arg("test_data/iyAndFlav1/small/small_test.curated.tpf")
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    assert!(String::from_utf8(output)
+        .unwrap()
+        .contains("UNCOVERED: SCAFFOLD_1:10-74 (65 bp)"));
+}
+
+#[test]
+fn check_reverse_complement_keeps_masking_and_iupac() {
+    assert_eq!(reverse_complement(b"ATGCatgc").unwrap(), b"gcatGCAT");
+    assert_eq!(reverse_complement(b"RYKMBVDHSWN").unwrap(), b"NWSDHBVKMRY");
+    assert_eq!(reverse_complement(b"acgun-").unwrap(), b"-nacgt");
+    assert_eq!(complement_base(b'r'), Some(b'y'));
+    assert_eq!(complement_base(b'X'), None);
+
+    let error = reverse_complement(b"ACXGT").unwrap_err().to_string();
+    assert_eq!(
+        error,
+        "Unexpected character 'X' at position 3, not an IUPAC base"
+    );
+
+    assert_eq!(orient(b"AAAcccGGt", "MINUS").unwrap(), b"aCCgggTTT");
+}
+
+#[test]
+fn check_curate_debug_trace_location() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta = fs::canonicalize("test_data/iyAndFlav1/small/small_test.fa").unwrap();
+    let tpf = fs::canonicalize("test_data/iyAndFlav1/small/small_test.curated.tpf").unwrap();
+    let trace = dir.path().join("trace.tsv");
+
+    // Next to the output by default, nothing in the working directory
+    fs::create_dir(dir.path().join("out")).unwrap();
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .current_dir(dir.path())
+        .arg("curate")
+        .arg("-f")
+        .arg(&fasta)
+        .arg("-t")
+        .arg(&tpf)
+        .arg("-o")
+        .arg(dir.path().join("out/first.fa"))
+        .assert()
+        .success();
+    assert!(dir.path().join("out/first.debug.tsv").exists());
+    assert!(!dir.path().join("debug.txt").exists());
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .current_dir(dir.path())
+        .arg("curate")
+        .arg("-f")
+        .arg(&fasta)
+        .arg("-t")
+        .arg(&tpf)
+        .arg("-o")
+        .arg(dir.path().join("out/second.fa"))
+        .arg("-d")
+        .arg(&trace)
+        .assert()
+        .success();
+    assert!(fs::read_to_string(&trace)
+        .unwrap()
+        .starts_with("#new_scaffold\tnew_start\tnew_end\tpart"));
+    assert!(!dir.path().join("out/second.debug.tsv").exists());
+
+    Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .current_dir(dir.path())
+        .arg("curate")
+        .arg("-f")
+        .arg(&fasta)
+        .arg("-t")
+        .arg(&tpf)
+        .arg("-o")
+        .arg(dir.path().join("out/third.fa"))
+        .arg("-D")
+        .arg("true")
+        .assert()
+        .success();
+    assert!(!dir.path().join("out/third.debug.tsv").exists());
+}
+
+#[test]
+fn check_curate_refuses_plain_gzip() {
+    let dir = Builder::new().prefix("local_tests").tempdir().unwrap();
+    let fasta_path = dir.path().join("input.fa.gz");
+    let output = dir.path().join("output.fa");
+    let mut encoder = GzEncoder::new(File::create(&fasta_path).unwrap(), Compression::default());
+    encoder
+        .write_all(&fs::read("test_data/iyAndFlav1/small/small_test.fa").unwrap())
+        .unwrap();
+    encoder.finish().unwrap();
+
+    let assert = Command::cargo_bin("fasta_manipulation")
+        .unwrap()
+        .arg("curate")
+        .arg("-f")
+        .arg(&fasta_path)
+        .arg("-t")
+        .arg("test_data/iyAndFlav1/small/small_test.curated.tpf")
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .code(1);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert!(stderr.contains("IS GZIP COMPRESSED, recompress it with bgzip"));
+    assert!(!stderr.contains("panicked"));
+    assert!(!output.exists());
 }